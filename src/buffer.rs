@@ -1,166 +1,457 @@
-use crate::{err, error::DnsError};
+use std::collections::HashMap;
 
-#[derive(Debug)]
+use crate::{
+    dns::name::DomainName,
+    err,
+    error::{DnsError, Malformation},
+};
+
+/// Classic DNS over UDP limit without EDNS (RFC 1035 4.2.1).
+pub const UDP_PACKET_SIZE: usize = 512;
+/// Largest message representable, bounded by the 16 bit TCP length prefix.
+pub const MAX_PACKET_SIZE: usize = u16::MAX as usize;
+/// Compression pointers carry a 14 bit offset (RFC 1035 4.1.4).
+const MAX_POINTER_OFFSET: usize = 0x3fff;
+/// Wire length of a name including length octets and the root label.
+pub const MAX_NAME_LENGTH: usize = 255;
+
+/// Source of wire-format bytes with random access, needed to follow
+/// compression pointers. Every accessor is bounds-checked and reports the
+/// offending offset.
+pub trait DnsRead {
+    fn pos(&self) -> usize;
+
+    /// Total length of the message.
+    fn len(&self) -> usize;
+
+    fn seek(&mut self, offset: usize) -> Result<(), DnsError>;
+
+    fn get(&self, offset: usize) -> Result<u8, DnsError>;
+
+    fn get_range(&self, start: usize, len: usize) -> Result<&[u8], DnsError>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn step(&mut self, offset: usize) -> Result<(), DnsError> {
+        self.seek(self.pos() + offset)
+    }
+
+    fn read(&mut self) -> Result<u8, DnsError> {
+        let res = self.get(self.pos())?;
+        self.step(1)?;
+        Ok(res)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, DnsError> {
+        let res = ((self.read()? as u16) << 8) | self.read()? as u16;
+        Ok(res)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, DnsError> {
+        let res = ((self.read()? as u32) << 24)
+            | ((self.read()? as u32) << 16)
+            | ((self.read()? as u32) << 8)
+            | self.read()? as u32;
+        Ok(res)
+    }
+
+    fn read_qname(&mut self, outname: &mut DomainName) -> Result<(), DnsError> {
+        self.visit_qname(|label| outname.push_label(label.to_vec()))
+    }
+
+    /// Walks the name at the current position, handing each label to `f`,
+    /// and leaves the cursor just past it.
+    ///
+    /// Pointers must point strictly before the labels currently being read,
+    /// which rules out forward pointers and loops without a jump limit.
+    fn visit_qname<F>(&mut self, mut f: F) -> Result<(), DnsError>
+    where
+        F: FnMut(&[u8]) -> Result<(), DnsError>,
+    {
+        let mut pos = self.pos();
+        let mut segment_start = pos;
+        let mut end = None;
+        let mut name_len = 1;
+
+        loop {
+            let len = self.get(pos)?;
+
+            match len & 0xc0 {
+                0xc0 => {
+                    let byte2 = self.get(pos + 1)? as usize;
+                    let target = (((len & 0x3f) as usize) << 8) | byte2;
+                    if target >= pos {
+                        return Err(err!(pos => Malformation::ForwardPointer { target }));
+                    }
+                    if target >= segment_start {
+                        return Err(err!(pos => Malformation::PointerLoop { target }));
+                    }
+
+                    end.get_or_insert(pos + 2);
+                    segment_start = target;
+                    pos = target;
+                }
+                0x00 => {
+                    pos += 1;
+                    if len == 0 {
+                        break;
+                    }
+
+                    name_len += 1 + len as usize;
+                    if name_len > MAX_NAME_LENGTH {
+                        return Err(err!(pos - 1 => Malformation::NameTooLong));
+                    }
+
+                    f(self.get_range(pos, len as usize)?)?;
+                    pos += len as usize;
+                }
+                kind => return Err(err!(pos => Malformation::ReservedLabelType(kind))),
+            }
+        }
+
+        self.seek(end.unwrap_or(pos))
+    }
+}
+
+/// Sink for wire-format bytes. Implementors that can remember name offsets
+/// override the `*_name` hooks to get compression for free.
+pub trait DnsWrite {
+    fn pos(&self) -> usize;
+
+    fn write(&mut self, val: u8) -> Result<(), DnsError>;
+
+    fn set(&mut self, offset: usize, val: u8) -> Result<(), DnsError>;
+
+    /// Offset of a previously written name suffix.
+    fn name_offset(&self, _suffix: &DomainName) -> Option<usize> {
+        None
+    }
+
+    fn add_name(&mut self, _suffix: DomainName, _offset: usize) {}
+
+    /// Forgets every name remembered for compression. Call before writing
+    /// a new message into a reused buffer.
+    fn clear_names(&mut self) {}
+
+    fn write_u16(&mut self, val: u16) -> Result<(), DnsError> {
+        self.write((val >> 8) as u8)?;
+        self.write((val & 0xff) as u8)
+    }
+
+    fn write_u32(&mut self, val: u32) -> Result<(), DnsError> {
+        self.write(((val >> 24) & 0xff) as u8)?;
+        self.write(((val >> 16) & 0xff) as u8)?;
+        self.write(((val >> 8) & 0xff) as u8)?;
+        self.write((val & 0xff) as u8)
+    }
+
+    fn set_u16(&mut self, offset: usize, val: u16) -> Result<(), DnsError> {
+        self.set(offset, (val >> 8) as u8)?;
+        self.set(offset + 1, (val & 0xff) as u8)
+    }
+
+    /// Writes `qname`, replacing the longest suffix already present in the
+    /// message with a compression pointer.
+    fn write_qname(&mut self, qname: &DomainName) -> Result<(), DnsError> {
+        write_labels(self, qname, true)
+    }
+
+    /// Writes `qname` as plain labels. Needed for RDATA of types that are
+    /// not allowed to carry compressed names (RFC 3597 section 4).
+    fn write_qname_uncompressed(&mut self, qname: &DomainName) -> Result<(), DnsError> {
+        write_labels(self, qname, false)
+    }
+}
+
+fn write_labels<W: DnsWrite + ?Sized>(
+    buffer: &mut W,
+    qname: &DomainName,
+    compress: bool,
+) -> Result<(), DnsError> {
+    for (i, label) in qname.labels().enumerate() {
+        if compress {
+            let suffix = qname.suffix(i);
+            if let Some(offset) = buffer.name_offset(&suffix) {
+                return buffer.write_u16(0xc000 | offset as u16);
+            }
+            let pos = buffer.pos();
+            if pos <= MAX_POINTER_OFFSET {
+                buffer.add_name(suffix, pos);
+            }
+        }
+
+        buffer.write(label.len() as u8)?;
+        for b in label {
+            buffer.write(*b)?;
+        }
+    }
+    buffer.write(0)
+}
+
+#[derive(Debug, Clone)]
 pub struct PacketBuffer {
-    pub buf: [u8; 512],
+    pub buf: Vec<u8>,
     pos: usize,
+    /// Name suffixes already written, keyed to their offset.
+    names: HashMap<DomainName, usize>,
 }
 
 impl Default for PacketBuffer {
     fn default() -> Self {
+        Self::new(UDP_PACKET_SIZE)
+    }
+}
+
+impl From<Vec<u8>> for PacketBuffer {
+    fn from(buf: Vec<u8>) -> Self {
         Self {
-            buf: [0; 512],
+            buf,
             pos: 0,
+            names: HashMap::new(),
         }
     }
 }
 
+impl From<&[u8]> for PacketBuffer {
+    fn from(buf: &[u8]) -> Self {
+        Self::from(buf.to_vec())
+    }
+}
+
 impl PacketBuffer {
+    /// Creates a zeroed buffer of `size` bytes, e.g. an EDNS payload size
+    /// or a TCP length prefix. Writes past the end grow the buffer up to
+    /// `MAX_PACKET_SIZE`.
+    pub fn new(size: usize) -> Self {
+        Self {
+            buf: vec![0; size.min(MAX_PACKET_SIZE)],
+            pos: 0,
+            names: HashMap::new(),
+        }
+    }
+
     pub fn pos(&self) -> usize {
         self.pos
     }
 
-    pub fn step(&mut self, offset: usize) -> Result<(), DnsError> {
-        self.pos += offset;
-        Ok(())
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Shrinks the buffer to the `len` bytes actually received.
+    pub fn truncate(&mut self, len: usize) {
+        self.buf.truncate(len);
+        self.pos = self.pos.min(len);
     }
+}
+
+impl DnsRead for PacketBuffer {
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    fn seek(&mut self, offset: usize) -> Result<(), DnsError> {
+        if offset > self.buf.len() {
+            return Err(err!(self.buf.len() => Malformation::Truncated));
+        }
 
-    pub fn seek(&mut self, offset: usize) -> Result<(), DnsError> {
         self.pos = offset;
         Ok(())
     }
 
-    pub fn read(&mut self) -> Result<u8, DnsError> {
-        if self.pos >= 512 {
+    fn get(&self, offset: usize) -> Result<u8, DnsError> {
+        if offset >= self.buf.len() {
+            return Err(err!(offset => Malformation::Truncated));
+        }
+
+        Ok(self.buf[offset])
+    }
+
+    fn get_range(&self, start: usize, len: usize) -> Result<&[u8], DnsError> {
+        if start + len > self.buf.len() {
+            return Err(err!(start => Malformation::Truncated));
+        }
+
+        Ok(&self.buf[start..start + len])
+    }
+}
+
+impl DnsWrite for PacketBuffer {
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    fn write(&mut self, val: u8) -> Result<(), DnsError> {
+        if self.pos >= MAX_PACKET_SIZE {
             return Err(err!(OutOfBounds));
         }
 
-        let res = self.buf[self.pos];
+        if self.pos >= self.buf.len() {
+            self.buf.resize(self.pos + 1, 0);
+        }
+
+        self.buf[self.pos] = val;
         self.pos += 1;
-        Ok(res)
+        Ok(())
     }
 
-    pub fn get(&self, offset: usize) -> Result<u8, DnsError> {
-        if offset >= 512 {
+    fn set(&mut self, offset: usize, val: u8) -> Result<(), DnsError> {
+        if offset >= self.buf.len() {
             return Err(err!(OutOfBounds));
         }
 
-        Ok(self.buf[offset])
+        self.buf[offset] = val;
+        Ok(())
     }
 
-    pub fn set(&mut self, offset: usize, val: u8) -> Result<(), DnsError> {
-        self.buf[offset] = val;
+    fn name_offset(&self, suffix: &DomainName) -> Option<usize> {
+        self.names.get(suffix).copied()
+    }
 
-        Ok(())
+    fn add_name(&mut self, suffix: DomainName, offset: usize) {
+        self.names.insert(suffix, offset);
     }
 
-    pub fn set_u16(&mut self, offset: usize, val: u16) -> Result<(), DnsError> {
-        self.set(offset, (val >> 8) as u8)?;
-        self.set(offset + 1, (val & 0xff) as u8)
+    fn clear_names(&mut self) {
+        self.names.clear();
     }
+}
 
-    pub fn get_range(&mut self, start: usize, len: usize) -> Result<&[u8], DnsError> {
-        if start + len >= 512 {
-            return Err(err!(OutOfBounds));
-        }
+/// Zero-copy reader over a borrowed message.
+#[derive(Debug, Clone)]
+pub struct SliceBuffer<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
 
-        Ok(&self.buf[start..start + len])
+impl<'a> SliceBuffer<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
     }
+}
 
-    pub fn read_u16(&mut self) -> Result<u16, DnsError> {
-        let res = ((self.read()? as u16) << 8) | self.read()? as u16;
-        Ok(res)
+impl DnsRead for SliceBuffer<'_> {
+    fn pos(&self) -> usize {
+        self.pos
     }
 
-    pub fn read_u32(&mut self) -> Result<u32, DnsError> {
-        let res = ((self.read()? as u32) << 24)
-            | ((self.read()? as u32) << 16)
-            | ((self.read()? as u32) << 8)
-            | self.read()? as u32;
-        Ok(res)
+    fn len(&self) -> usize {
+        self.buf.len()
     }
 
-    pub fn write(&mut self, val: u8) -> Result<(), DnsError> {
-        if self.pos >= 512 {
-            return Err(err!(OutOfBounds));
+    fn seek(&mut self, offset: usize) -> Result<(), DnsError> {
+        if offset > self.buf.len() {
+            return Err(err!(self.buf.len() => Malformation::Truncated));
         }
 
-        self.buf[self.pos] = val;
-        self.pos += 1;
+        self.pos = offset;
         Ok(())
     }
 
-    pub fn write_u16(&mut self, val: u16) -> Result<(), DnsError> {
-        self.write((val >> 8) as u8)?;
-        self.write((val & 0xff) as u8)
+    fn get(&self, offset: usize) -> Result<u8, DnsError> {
+        self.buf
+            .get(offset)
+            .copied()
+            .ok_or_else(|| err!(offset => Malformation::Truncated))
     }
 
-    pub fn write_u32(&mut self, val: u32) -> Result<(), DnsError> {
-        self.write(((val >> 24) & 0xff) as u8)?;
-        self.write(((val >> 16) & 0xff) as u8)?;
-        self.write(((val >> 8) & 0xff) as u8)?;
-        self.write((val & 0xff) as u8)
+    fn get_range(&self, start: usize, len: usize) -> Result<&[u8], DnsError> {
+        self.buf
+            .get(start..start + len)
+            .ok_or_else(|| err!(start => Malformation::Truncated))
     }
+}
 
-    pub fn read_qname(&mut self, outstr: &mut String) -> Result<(), DnsError> {
-        let mut pos = self.pos();
+/// Writer into a caller provided slice, e.g. a stack array. Fails with
+/// `OutOfBounds` once the slice is full.
+#[derive(Debug)]
+pub struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
 
-        let mut jumped = false;
-        let max = 5;
-        let mut jumps = 0;
+impl<'a> SliceWriter<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
 
-        let mut delim = "";
-        loop {
-            if jumps > max {
-                return Err(err!(JumpsExceed));
-            }
+    /// The bytes written so far.
+    pub fn written(&self) -> &[u8] {
+        &self.buf[..self.pos]
+    }
+}
 
-            let len = self.get(pos)?;
+impl DnsWrite for SliceWriter<'_> {
+    fn pos(&self) -> usize {
+        self.pos
+    }
 
-            if (len & 0xc0) == 0xc0 {
-                if !jumped {
-                    self.seek(pos + 2)?;
-                }
+    fn write(&mut self, val: u8) -> Result<(), DnsError> {
+        let slot = self
+            .buf
+            .get_mut(self.pos)
+            .ok_or_else(|| err!(OutOfBounds))?;
+        *slot = val;
+        self.pos += 1;
+        Ok(())
+    }
 
-                let byte2 = self.get(pos + 1)? as u16;
-                let offset = ((len as u16 ^ 0xc0) << 8) | byte2;
-                pos = offset as usize;
-
-                jumped = true;
-                jumps += 1;
-                continue;
-            } else {
-                pos += 1;
-                if len == 0 {
-                    break;
-                }
-                outstr.push_str(delim);
-                let str = self.get_range(pos, len as usize)?;
-                outstr.push_str(&String::from_utf8_lossy(str).to_lowercase());
-                delim = ".";
-                pos += len as usize;
-            }
-        }
+    fn set(&mut self, offset: usize, val: u8) -> Result<(), DnsError> {
+        let slot = self.buf.get_mut(offset).ok_or_else(|| err!(OutOfBounds))?;
+        *slot = val;
+        Ok(())
+    }
+}
+
+impl DnsWrite for Vec<u8> {
+    fn pos(&self) -> usize {
+        self.len()
+    }
 
-        if !jumped {
-            self.seek(pos)?;
+    fn write(&mut self, val: u8) -> Result<(), DnsError> {
+        if self.len() >= MAX_PACKET_SIZE {
+            return Err(err!(OutOfBounds));
         }
 
+        self.push(val);
         Ok(())
     }
 
-    pub fn write_qname(&mut self, qname: &str) -> Result<(), DnsError> {
-        for label in qname.split('.') {
-            let len = label.len();
-            if len > 0x3f {
-                return Err(err!(LabelLengthExceed));
-            }
+    fn set(&mut self, offset: usize, val: u8) -> Result<(), DnsError> {
+        let slot = self.get_mut(offset).ok_or_else(|| err!(OutOfBounds))?;
+        *slot = val;
+        Ok(())
+    }
+}
 
-            self.write(len as u8)?;
-            for b in label.as_bytes() {
-                self.write(*b)?;
-            }
+#[cfg(feature = "bytes")]
+impl DnsWrite for bytes::BytesMut {
+    fn pos(&self) -> usize {
+        self.len()
+    }
+
+    fn write(&mut self, val: u8) -> Result<(), DnsError> {
+        if self.len() >= MAX_PACKET_SIZE {
+            return Err(err!(OutOfBounds));
         }
-        self.write(0)
+
+        self.extend_from_slice(&[val]);
+        Ok(())
+    }
+
+    fn set(&mut self, offset: usize, val: u8) -> Result<(), DnsError> {
+        let slot = self.get_mut(offset).ok_or_else(|| err!(OutOfBounds))?;
+        *slot = val;
+        Ok(())
     }
 }
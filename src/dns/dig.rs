@@ -0,0 +1,133 @@
+//! Packet printer in the layout of dig(1).
+
+use std::fmt;
+
+use super::{edns::Edns, record::DnsRecord, DnsPacket};
+
+/// Formats a packet the way dig prints a response, see [`DnsPacket::dig`].
+#[derive(Debug, Clone, Copy)]
+pub struct Dig<'a>(&'a DnsPacket);
+
+impl DnsPacket {
+    pub fn dig(&self) -> Dig<'_> {
+        Dig(self)
+    }
+}
+
+fn option_name(code: u16) -> Option<&'static str> {
+    match code {
+        3 => Some("NSID"),
+        8 => Some("CLIENT-SUBNET"),
+        9 => Some("EXPIRE"),
+        10 => Some("COOKIE"),
+        11 => Some("KEEPALIVE"),
+        12 => Some("PADDING"),
+        15 => Some("EDE"),
+        _ => None,
+    }
+}
+
+impl Dig<'_> {
+    fn fmt_opt(&self, f: &mut fmt::Formatter<'_>, edns: &Edns) -> fmt::Result {
+        writeln!(f, ";; OPT PSEUDOSECTION:")?;
+        write!(f, "; EDNS: version: {}, flags:", edns.version)?;
+        if edns.dnssec_ok {
+            write!(f, " do")?;
+        }
+        writeln!(f, "; udp: {}", edns.udp_payload_size)?;
+
+        for option in &edns.options {
+            match option_name(option.code()) {
+                Some(name) => write!(f, "; {name}:")?,
+                None => write!(f, "; OPT={}:", option.code())?,
+            }
+            let value = option.to_string();
+            if !value.is_empty() {
+                write!(f, " {value}")?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)
+    }
+
+    fn fmt_section(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        title: &str,
+        records: &[DnsRecord],
+    ) -> fmt::Result {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f, ";; {title} SECTION:")?;
+        for record in records {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}",
+                record.domain(),
+                record.ttl(),
+                record.class(),
+                record.qtype(),
+                record.rdata()
+            )?;
+        }
+        writeln!(f)
+    }
+}
+
+impl fmt::Display for Dig<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let packet = self.0;
+        let header = &packet.header;
+        writeln!(
+            f,
+            ";; ->>HEADER<<- opcode: {}, status: {}, id: {}",
+            header.opcode, header.rescode, header.id
+        )?;
+
+        write!(f, ";; flags:")?;
+        let flags = [
+            (header.response, "qr"),
+            (header.authorative_answer, "aa"),
+            (header.truncated_message, "tc"),
+            (header.recursion_desired, "rd"),
+            (header.recursion_available, "ra"),
+            (header.z, "z"),
+            (header.authed_data, "ad"),
+            (header.checking_disabled, "cd"),
+        ];
+        for (_, flag) in flags.iter().filter(|(set, _)| *set) {
+            write!(f, " {flag}")?;
+        }
+        writeln!(
+            f,
+            "; QUERY: {}, ANSWER: {}, AUTHORITY: {}, ADDITIONAL: {}",
+            packet.questions.len(),
+            packet.answers.len(),
+            packet.authorities.len(),
+            packet.resources.len() + packet.edns.is_some() as usize
+        )?;
+        writeln!(f)?;
+
+        if let Some(edns) = &packet.edns {
+            self.fmt_opt(f, edns)?;
+        }
+
+        if !packet.questions.is_empty() {
+            writeln!(f, ";; QUESTION SECTION:")?;
+            for question in &packet.questions {
+                writeln!(
+                    f,
+                    ";{}\t\t{}\t{}",
+                    question.name, question.qclass, question.qtype
+                )?;
+            }
+            writeln!(f)?;
+        }
+
+        self.fmt_section(f, "ANSWER", &packet.answers)?;
+        self.fmt_section(f, "AUTHORITY", &packet.authorities)?;
+        self.fmt_section(f, "ADDITIONAL", &packet.resources)
+    }
+}
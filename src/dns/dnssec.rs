@@ -0,0 +1,188 @@
+//! Helpers for the DNSSEC record types (RFC 4034, RFC 5155): type
+//! bitmaps, signature times and key tags.
+
+use std::fmt;
+
+use crate::{buffer::DnsWrite, error::DnsError};
+
+use super::{record::DnsRecord, QueryType};
+
+/// Decodes the type bitmap filling the rest of an NSEC or NSEC3 record
+/// (RFC 4034 section 4.1.2). `None` if windows are out of order, a
+/// bitmap is empty or longer than 32 octets, or ends in a zero octet.
+pub(crate) fn read_type_bitmap(mut data: &[u8]) -> Option<Vec<QueryType>> {
+    let mut types = Vec::new();
+    let mut last_window = None;
+    while !data.is_empty() {
+        let [window, len, rest @ ..] = data else {
+            return None;
+        };
+        let len = *len as usize;
+        if last_window.is_some_and(|last| last >= *window)
+            || !(1..=32).contains(&len)
+            || len > rest.len()
+            || rest[len - 1] == 0
+        {
+            return None;
+        }
+
+        for (i, &octet) in rest[..len].iter().enumerate() {
+            for bit in (0..8).filter(|bit| octet & (0x80 >> bit) != 0) {
+                let low = (i * 8 + bit) as u16;
+                types.push(QueryType::from((*window as u16) << 8 | low));
+            }
+        }
+        last_window = Some(*window);
+        data = &rest[len..];
+    }
+    Some(types)
+}
+
+/// Encodes `types` as a type bitmap. Order and repeats do not matter.
+pub(crate) fn write_type_bitmap<W: DnsWrite>(
+    buffer: &mut W,
+    types: &[QueryType],
+) -> Result<(), DnsError> {
+    let mut numbers: Vec<u16> = types.iter().map(|&qtype| qtype.into()).collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    for window in numbers.chunk_by(|a, b| a >> 8 == b >> 8) {
+        let mut bitmap = [0u8; 32];
+        for &number in window {
+            let low = (number & 0xff) as usize;
+            bitmap[low / 8] |= 0x80 >> (low % 8);
+        }
+        let len = (window[window.len() - 1] & 0xff) as usize / 8 + 1;
+
+        buffer.write((window[0] >> 8) as u8)?;
+        buffer.write(len as u8)?;
+        for &b in &bitmap[..len] {
+            buffer.write(b)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes types as a space separated list of mnemonics, each preceded
+/// by a space.
+pub(crate) fn fmt_types(f: &mut fmt::Formatter<'_>, types: &[QueryType]) -> fmt::Result {
+    for qtype in types {
+        write!(f, " {qtype}")?;
+    }
+    Ok(())
+}
+
+/// Days since 1970-01-01 to a proleptic Gregorian date, after Howard
+/// Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// The inverse of `civil_from_days`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// An RRSIG inception or expiration time, written `YYYYMMDDHHmmSS` in UTC
+/// (RFC 4034 section 3.2).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SignatureTime(pub u32);
+
+impl fmt::Display for SignatureTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0 as i64;
+        let (year, month, day) = civil_from_days(secs / 86_400);
+        let secs = secs % 86_400;
+        write!(
+            f,
+            "{year:04}{month:02}{day:02}{:02}{:02}{:02}",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+}
+
+impl SignatureTime {
+    /// Parses `YYYYMMDDHHmmSS` or, for any other length, seconds since
+    /// the epoch as a decimal number. Dates past 2106 do not fit.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        if !text.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        if text.len() != 14 {
+            return text.parse().ok().map(Self);
+        }
+
+        let field = |range: std::ops::Range<usize>| text[range].parse::<u32>().ok();
+        let (year, month, day) = (field(0..4)?, field(4..6)?, field(6..8)?);
+        let (hour, minute, second) = (field(8..10)?, field(10..12)?, field(12..14)?);
+
+        let days = days_from_civil(year as i64, month, day);
+        let valid = (1..=12).contains(&month)
+            && day >= 1
+            && civil_from_days(days) == (year as i64, month, day)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+        if !valid {
+            return None;
+        }
+
+        let secs = days * 86_400 + (hour * 3600 + minute * 60 + second) as i64;
+        u32::try_from(secs).ok().map(Self)
+    }
+}
+
+impl DnsRecord {
+    /// The key tag of a DNSKEY record (RFC 4034 appendix B), which DS and
+    /// RRSIG records use to refer to it. `None` for other types.
+    pub fn key_tag(&self) -> Option<u16> {
+        let Self::DNSKEY {
+            flags,
+            protocol,
+            algorithm,
+            public_key,
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        let mut rdata = flags.to_be_bytes().to_vec();
+        rdata.extend_from_slice(&[*protocol, *algorithm]);
+        rdata.extend_from_slice(public_key);
+
+        // RSA/MD5 keys use bits of the modulus instead (appendix B.1).
+        if *algorithm == 1 {
+            let len = rdata.len();
+            return Some(u16::from_be_bytes([rdata[len - 3], rdata[len - 2]]));
+        }
+
+        let mut acc: u32 = 0;
+        for (i, &b) in rdata.iter().enumerate() {
+            acc += match i % 2 {
+                0 => (b as u32) << 8,
+                _ => b as u32,
+            };
+        }
+        acc += acc >> 16 & 0xffff;
+        Some(acc as u16)
+    }
+}
@@ -0,0 +1,326 @@
+//! EDNS(0) carried in the OPT pseudo-record (RFC 6891) and the options it
+//! holds.
+
+use std::{fmt, net::IpAddr};
+
+use crate::{buffer::UDP_PACKET_SIZE, error::DnsError};
+
+use super::{encoding, name::DomainName, presentation::invalid, record::DnsRecord, DnsClass};
+
+/// OPT pseudo-record type.
+pub const OPT: u16 = 41;
+
+/// The payload size we advertise and accept, the DNS Flag Day 2020 value
+/// that avoids IP fragmentation on common paths.
+pub const EDNS_PAYLOAD_SIZE: u16 = 1232;
+
+/// The DO bit in the OPT TTL (RFC 3225).
+const DNSSEC_OK: u32 = 0x8000;
+
+/// The OPT record of a message. Its owner is always the root, its class
+/// the payload size and its TTL the upper RCODE bits, version and flags.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Edns {
+    /// Largest UDP payload the sender can reassemble.
+    pub udp_payload_size: u16,
+    /// Upper 8 bits of the 12 bit RCODE. Reading a packet merges them into
+    /// `header.rescode`, and writing one takes them back from there.
+    pub extended_rcode: u8,
+    pub version: u8,
+    /// The sender wants DNSSEC records (RFC 3225).
+    pub dnssec_ok: bool,
+    pub options: Vec<EdnsOption>,
+}
+
+impl Default for Edns {
+    fn default() -> Self {
+        Self {
+            udp_payload_size: EDNS_PAYLOAD_SIZE,
+            extended_rcode: 0,
+            version: 0,
+            dnssec_ok: false,
+            options: Vec::new(),
+        }
+    }
+}
+
+/// One option of the OPT RDATA. Values that break their RFC's rules are
+/// kept as `Unknown` under their own code.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EdnsOption {
+    /// Name server identifier (RFC 5001), empty in a query.
+    Nsid(Vec<u8>),
+    /// The client's network (RFC 7871). Address bits past `source_prefix`
+    /// are zero.
+    ClientSubnet {
+        source_prefix: u8,
+        scope_prefix: u8,
+        address: IpAddr,
+    },
+    /// DNS cookie (RFC 7873). The server cookie is empty or 8 to 32 bytes.
+    Cookie {
+        client: [u8; 8],
+        server: Vec<u8>,
+    },
+    /// TCP idle timeout in units of 100 ms (RFC 7828), absent in queries.
+    Keepalive(Option<u16>),
+    /// This many zero bytes (RFC 7830).
+    Padding(u16),
+    /// Extended DNS error (RFC 8914).
+    Ede {
+        code: u16,
+        text: String,
+    },
+    Unknown {
+        code: u16,
+        data: Vec<u8>,
+    },
+}
+
+/// The bits of the last address octet past a client-subnet prefix.
+fn host_bits(prefix: u8) -> u8 {
+    match prefix % 8 {
+        0 => 0,
+        used => 0xff >> used,
+    }
+}
+
+impl EdnsOption {
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::Nsid(_) => 3,
+            Self::ClientSubnet { .. } => 8,
+            Self::Cookie { .. } => 10,
+            Self::Keepalive(_) => 11,
+            Self::Padding(_) => 12,
+            Self::Ede { .. } => 15,
+            Self::Unknown { code, .. } => *code,
+        }
+    }
+
+    fn from_wire(code: u16, data: &[u8]) -> Self {
+        Self::decode(code, data).unwrap_or_else(|| Self::Unknown {
+            code,
+            data: data.to_vec(),
+        })
+    }
+
+    /// Decodes a known option, or `None` if the code is unknown or the
+    /// value malformed.
+    fn decode(code: u16, data: &[u8]) -> Option<Self> {
+        let option = match code {
+            3 => Self::Nsid(data.to_vec()),
+            8 => {
+                let [f0, f1, source_prefix, scope_prefix, addr @ ..] = data else {
+                    return None;
+                };
+                let family = u16::from_be_bytes([*f0, *f1]);
+                let bits = match family {
+                    1 => 32,
+                    2 => 128,
+                    _ => return None,
+                };
+                if *source_prefix > bits
+                    || *scope_prefix > bits
+                    || addr.len() != (*source_prefix as usize).div_ceil(8)
+                    || addr
+                        .last()
+                        .is_some_and(|&last| last & host_bits(*source_prefix) != 0)
+                {
+                    return None;
+                }
+                let address = match family {
+                    1 => {
+                        let mut octets = [0; 4];
+                        octets[..addr.len()].copy_from_slice(addr);
+                        IpAddr::from(octets)
+                    }
+                    _ => {
+                        let mut octets = [0; 16];
+                        octets[..addr.len()].copy_from_slice(addr);
+                        IpAddr::from(octets)
+                    }
+                };
+                Self::ClientSubnet {
+                    source_prefix: *source_prefix,
+                    scope_prefix: *scope_prefix,
+                    address,
+                }
+            }
+            10 if data.len() == 8 || (16..=40).contains(&data.len()) => Self::Cookie {
+                client: data[..8].try_into().unwrap(),
+                server: data[8..].to_vec(),
+            },
+            11 => match data {
+                [] => Self::Keepalive(None),
+                [hi, lo] => Self::Keepalive(Some(u16::from_be_bytes([*hi, *lo]))),
+                _ => return None,
+            },
+            12 if data.iter().all(|&b| b == 0) => Self::Padding(data.len() as u16),
+            15 if data.len() >= 2 => Self::Ede {
+                code: u16::from_be_bytes([data[0], data[1]]),
+                text: String::from_utf8(data[2..].to_vec()).ok()?,
+            },
+            _ => return None,
+        };
+        Some(option)
+    }
+
+    /// The option value, checking the constraints `decode` enforces.
+    fn to_wire(&self) -> Result<Vec<u8>, DnsError> {
+        let mut data = Vec::new();
+        match self {
+            Self::Nsid(id) => data.extend_from_slice(id),
+            Self::ClientSubnet {
+                source_prefix,
+                scope_prefix,
+                address,
+            } => {
+                let (family, octets) = match address {
+                    IpAddr::V4(addr) => (1u16, addr.octets().to_vec()),
+                    IpAddr::V6(addr) => (2u16, addr.octets().to_vec()),
+                };
+                let bits = octets.len() * 8;
+                if *source_prefix as usize > bits || *scope_prefix as usize > bits {
+                    return Err(invalid(format!(
+                        "prefix /{source_prefix}/{scope_prefix} too long for {address}"
+                    )));
+                }
+                let len = (*source_prefix as usize).div_ceil(8);
+                let masked = octets[len..].iter().all(|&b| b == 0)
+                    && octets[..len]
+                        .last()
+                        .is_none_or(|&last| last & host_bits(*source_prefix) == 0);
+                if !masked {
+                    return Err(invalid(format!(
+                        "{address} has bits set past /{source_prefix}"
+                    )));
+                }
+
+                data.extend_from_slice(&family.to_be_bytes());
+                data.extend_from_slice(&[*source_prefix, *scope_prefix]);
+                data.extend_from_slice(&octets[..len]);
+            }
+            Self::Cookie { client, server } => {
+                if !server.is_empty() && !(8..=32).contains(&server.len()) {
+                    return Err(invalid(format!("server cookie of {} bytes", server.len())));
+                }
+                data.extend_from_slice(client);
+                data.extend_from_slice(server);
+            }
+            Self::Keepalive(timeout) => data.extend(timeout.iter().flat_map(|t| t.to_be_bytes())),
+            Self::Padding(len) => data.resize(*len as usize, 0),
+            Self::Ede { code, text } => {
+                data.extend_from_slice(&code.to_be_bytes());
+                data.extend_from_slice(text.as_bytes());
+            }
+            Self::Unknown { data: value, .. } => data.extend_from_slice(value),
+        }
+        Ok(data)
+    }
+}
+
+/// The value as dig shows it, without the option name.
+impl fmt::Display for EdnsOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nsid(id) => write!(f, "{}", encoding::to_hex(id)),
+            Self::ClientSubnet {
+                source_prefix,
+                scope_prefix,
+                address,
+            } => write!(f, "{address}/{source_prefix}/{scope_prefix}"),
+            Self::Cookie { client, server } => {
+                write!(
+                    f,
+                    "{}{}",
+                    encoding::to_hex(client),
+                    encoding::to_hex(server)
+                )
+            }
+            Self::Keepalive(None) => Ok(()),
+            Self::Keepalive(Some(timeout)) => {
+                write!(f, "{}.{} secs", timeout / 10, timeout % 10)
+            }
+            Self::Padding(len) => write!(f, "{len} bytes"),
+            Self::Ede { code, text } if text.is_empty() => write!(f, "{code}"),
+            Self::Ede { code, text } => write!(f, "{code} ({text:?})"),
+            Self::Unknown { data, .. } => write!(f, "{}", encoding::to_hex(data)),
+        }
+    }
+}
+
+impl Edns {
+    /// Decodes the class, TTL and RDATA of an OPT record. `None` if an
+    /// option runs past the end of the RDATA.
+    pub(crate) fn from_opt(class: DnsClass, ttl: u32, mut data: &[u8]) -> Option<Self> {
+        let mut options = Vec::new();
+        while !data.is_empty() {
+            let [c0, c1, l0, l1, rest @ ..] = data else {
+                return None;
+            };
+            let len = u16::from_be_bytes([*l0, *l1]) as usize;
+            if len > rest.len() {
+                return None;
+            }
+            options.push(EdnsOption::from_wire(
+                u16::from_be_bytes([*c0, *c1]),
+                &rest[..len],
+            ));
+            data = &rest[len..];
+        }
+
+        Some(Self {
+            udp_payload_size: class.into(),
+            extended_rcode: (ttl >> 24) as u8,
+            version: (ttl >> 16) as u8,
+            dnssec_ok: ttl & DNSSEC_OK != 0,
+            options,
+        })
+    }
+
+    /// Reads the EDNS fields out of an OPT record, `None` for any other
+    /// record or if the options are malformed.
+    pub(crate) fn from_record(record: &DnsRecord) -> Option<Self> {
+        match record {
+            DnsRecord::Unknown {
+                qtype: OPT,
+                data,
+                class,
+                ttl,
+                ..
+            } => Self::from_opt(*class, *ttl, data),
+            _ => None,
+        }
+    }
+
+    /// The OPT record carrying these fields.
+    pub(crate) fn to_record(&self) -> Result<DnsRecord, DnsError> {
+        let mut data = Vec::new();
+        for option in &self.options {
+            let value = option.to_wire()?;
+            let len = u16::try_from(value.len())
+                .map_err(|_| invalid(format!("option {} too long", option.code())))?;
+            data.extend_from_slice(&option.code().to_be_bytes());
+            data.extend_from_slice(&len.to_be_bytes());
+            data.extend_from_slice(&value);
+        }
+
+        Ok(DnsRecord::Unknown {
+            domain: DomainName::root(),
+            qtype: OPT,
+            data,
+            class: self.udp_payload_size.into(),
+            ttl: (self.extended_rcode as u32) << 24
+                | (self.version as u32) << 16
+                | if self.dnssec_ok { DNSSEC_OK } else { 0 },
+        })
+    }
+
+    /// Size of the UDP response to a query carrying this record: the
+    /// advertised payload size, raised to 512 (RFC 6891 section 6.2.5) and
+    /// capped at ours.
+    pub fn response_size(&self) -> usize {
+        (self.udp_payload_size as usize).clamp(UDP_PACKET_SIZE, EDNS_PAYLOAD_SIZE as usize)
+    }
+}
@@ -0,0 +1,122 @@
+//! Text encodings used by presentation formats.
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Lowercase hex without separators.
+pub fn to_hex(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() * 2);
+    for &b in data {
+        out.push(HEX_DIGITS[(b >> 4) as usize] as char);
+        out.push(HEX_DIGITS[(b & 0x0f) as usize] as char);
+    }
+    out
+}
+
+/// Decodes hex in either case. Whitespace is ignored since presentation
+/// formats allow long hex fields to be split.
+pub fn from_hex(text: &str) -> Option<Vec<u8>> {
+    let digits = text
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect::<Option<Vec<_>>>()?;
+
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair[1])
+            .collect(),
+    )
+}
+
+const BASE64_DIGITS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Padded base64 (RFC 4648 section 4).
+pub fn to_base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => out.push(BASE64_DIGITS[(bits >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => out.push('='),
+            }
+        }
+    }
+    out
+}
+
+/// Decodes padded base64, ignoring whitespace like `from_hex`.
+pub fn from_base64(text: &str) -> Option<Vec<u8>> {
+    let chars: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !chars.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(chars.len() / 4 * 3);
+    for (n, quad) in chars.chunks(4).enumerate() {
+        let last = n + 1 == chars.len() / 4;
+        let padding = quad.iter().rev().take_while(|&&b| b == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+
+        let mut bits = 0u32;
+        for (i, &c) in quad[..4 - padding].iter().enumerate() {
+            let digit = BASE64_DIGITS.iter().position(|&d| d == c)? as u32;
+            bits |= digit << (18 - 6 * i);
+        }
+        let bytes = bits.to_be_bytes();
+        out.extend_from_slice(&bytes[1..4 - padding]);
+    }
+    Some(out)
+}
+
+const BASE32HEX_DIGITS: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+/// Lowercase base32 with the extended hex alphabet and no padding, as NSEC3
+/// hashes are written (RFC 5155 section 3.3).
+pub fn to_base32hex(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut bits = 0u32;
+    let mut pending = 0;
+    for &b in data {
+        bits = (bits << 8) | b as u32;
+        pending += 8;
+        while pending >= 5 {
+            pending -= 5;
+            out.push(BASE32HEX_DIGITS[(bits >> pending & 0x1f) as usize] as char);
+        }
+    }
+    if pending > 0 {
+        out.push(BASE32HEX_DIGITS[(bits << (5 - pending) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+/// Decodes unpadded base32hex in either case. Leftover bits must be zero
+/// so every value has a single encoding.
+pub fn from_base32hex(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let mut bits = 0u32;
+    let mut pending = 0;
+    for c in text.bytes() {
+        let digit = (c as char).to_digit(32)?;
+        bits = (bits << 5) | digit;
+        pending += 5;
+        if pending >= 8 {
+            pending -= 8;
+            out.push((bits >> pending) as u8);
+        }
+    }
+    // 5 or more leftover bits would have been a whole digit of padding.
+    (pending < 5 && bits & ((1 << pending) - 1) == 0).then_some(out)
+}
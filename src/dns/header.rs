@@ -1,15 +1,18 @@
-use crate::{buffer::PacketBuffer, error::DnsError};
+use crate::{
+    buffer::{DnsRead, DnsWrite},
+    error::DnsError,
+};
 
-use super::ResCode;
+use super::{Opcode, ResCode};
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct DnsHeader {
     pub id: u16,
 
     pub recursion_desired: bool,
     pub truncated_message: bool,
     pub authorative_answer: bool,
-    pub opcode: u8,
+    pub opcode: Opcode,
     pub response: bool,
 
     pub rescode: ResCode,
@@ -25,7 +28,7 @@ pub struct DnsHeader {
 }
 
 impl DnsHeader {
-    pub fn read(&mut self, buffer: &mut PacketBuffer) -> Result<(), DnsError> {
+    pub fn read<R: DnsRead>(&mut self, buffer: &mut R) -> Result<(), DnsError> {
         self.id = buffer.read_u16()?;
 
         let flags = buffer.read_u16()?;
@@ -34,7 +37,7 @@ impl DnsHeader {
         self.recursion_desired = (a & (1 << 0)) > 0;
         self.truncated_message = (a & (1 << 1)) > 0;
         self.authorative_answer = (a & (1 << 2)) > 0;
-        self.opcode = (a >> 3) & 0x0f;
+        self.opcode = ((a >> 3) & 0x0f).into();
         self.response = (a & (1 << 7)) > 0;
 
         self.rescode = (b & 0x0f).into();
@@ -51,18 +54,19 @@ impl DnsHeader {
         Ok(())
     }
 
-    pub fn write(&mut self, buffer: &mut PacketBuffer) -> Result<(), DnsError> {
+    pub fn write<W: DnsWrite>(&mut self, buffer: &mut W) -> Result<(), DnsError> {
         buffer.write_u16(self.id)?;
 
         buffer.write(
             (self.recursion_desired as u8)
                 | ((self.truncated_message as u8) << 1)
-                | (self.opcode << 3)
+                | ((self.authorative_answer as u8) << 2)
+                | ((u8::from(self.opcode) & 0x0f) << 3)
                 | ((self.response as u8) << 7),
         )?;
 
         buffer.write(
-            (self.rescode as u8)
+            self.rescode.header_bits()
                 | ((self.checking_disabled as u8) << 4)
                 | ((self.authed_data as u8) << 5)
                 | ((self.z as u8) << 6)
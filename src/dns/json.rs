@@ -0,0 +1,486 @@
+//! JSON representation of messages following RFC 8427.
+//!
+//! Records are always emitted with `RDATAHEX`, which is lossless for every
+//! type, plus an `rdata<TYPE>` member in presentation format where RFC 8427
+//! defines one. Either form is accepted on input so fixtures can be written
+//! by hand.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    buffer::{DnsRead, SliceBuffer},
+    error::DnsError,
+};
+
+use super::{
+    edns::{Edns, OPT},
+    encoding,
+    header::DnsHeader,
+    name::DomainName,
+    question::DnsQuestion,
+    record::DnsRecord,
+    DnsClass, DnsPacket, QueryType, ResCode,
+};
+
+/// Booleans are written as 0/1 like the RFC 8427 examples, and read from
+/// either form.
+mod flag {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*value as u8)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Flag {
+            Bool(bool),
+            Int(u8),
+        }
+
+        match Flag::deserialize(deserializer)? {
+            Flag::Bool(value) => Ok(value),
+            Flag::Int(value) => Ok(value != 0),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct HeaderJson {
+    #[serde(rename = "ID", default)]
+    id: u16,
+    #[serde(rename = "QR", with = "flag", default)]
+    qr: bool,
+    #[serde(rename = "Opcode", default)]
+    opcode: u8,
+    #[serde(rename = "AA", with = "flag", default)]
+    aa: bool,
+    #[serde(rename = "TC", with = "flag", default)]
+    tc: bool,
+    #[serde(rename = "RD", with = "flag", default)]
+    rd: bool,
+    #[serde(rename = "RA", with = "flag", default)]
+    ra: bool,
+    #[serde(rename = "AD", with = "flag", default)]
+    ad: bool,
+    #[serde(rename = "CD", with = "flag", default)]
+    cd: bool,
+    #[serde(rename = "RCODE", default)]
+    rcode: u16,
+    #[serde(rename = "QDCOUNT", default)]
+    qdcount: u16,
+    #[serde(rename = "ANCOUNT", default)]
+    ancount: u16,
+    #[serde(rename = "NSCOUNT", default)]
+    nscount: u16,
+    #[serde(rename = "ARCOUNT", default)]
+    arcount: u16,
+}
+
+impl From<&DnsHeader> for HeaderJson {
+    fn from(header: &DnsHeader) -> Self {
+        Self {
+            id: header.id,
+            qr: header.response,
+            opcode: header.opcode.into(),
+            aa: header.authorative_answer,
+            tc: header.truncated_message,
+            rd: header.recursion_desired,
+            ra: header.recursion_available,
+            ad: header.authed_data,
+            cd: header.checking_disabled,
+            rcode: header.rescode.into(),
+            qdcount: header.questions,
+            ancount: header.answers,
+            nscount: header.authorative_entries,
+            arcount: header.resource_entries,
+        }
+    }
+}
+
+impl From<HeaderJson> for DnsHeader {
+    fn from(json: HeaderJson) -> Self {
+        Self {
+            id: json.id,
+            response: json.qr,
+            opcode: json.opcode.into(),
+            authorative_answer: json.aa,
+            truncated_message: json.tc,
+            recursion_desired: json.rd,
+            recursion_available: json.ra,
+            authed_data: json.ad,
+            checking_disabled: json.cd,
+            rescode: json.rcode.into(),
+            questions: json.qdcount,
+            answers: json.ancount,
+            authorative_entries: json.nscount,
+            resource_entries: json.arcount,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct QuestionJson {
+    #[serde(rename = "NAME")]
+    name: String,
+    #[serde(rename = "TYPE")]
+    qtype: u16,
+    #[serde(rename = "CLASS", default = "class_in")]
+    qclass: u16,
+}
+
+fn class_in() -> u16 {
+    DnsClass::IN.into()
+}
+
+impl From<&DnsQuestion> for QuestionJson {
+    fn from(question: &DnsQuestion) -> Self {
+        Self {
+            name: question.name.to_string(),
+            qtype: question.qtype.into(),
+            qclass: question.qclass.into(),
+        }
+    }
+}
+
+impl TryFrom<QuestionJson> for DnsQuestion {
+    type Error = DnsError;
+
+    fn try_from(json: QuestionJson) -> Result<Self, Self::Error> {
+        let mut question = DnsQuestion::new(json.name.parse()?, json.qtype.into());
+        question.qclass = json.qclass.into();
+        Ok(question)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordJson {
+    #[serde(rename = "NAME")]
+    name: String,
+    #[serde(rename = "TYPE")]
+    rtype: u16,
+    #[serde(rename = "CLASS", default = "class_in")]
+    class: u16,
+    #[serde(rename = "TTL", default)]
+    ttl: u32,
+    #[serde(rename = "RDLENGTH", skip_serializing_if = "Option::is_none", default)]
+    rdlength: Option<u16>,
+    #[serde(rename = "RDATAHEX", skip_serializing_if = "Option::is_none", default)]
+    rdata_hex: Option<String>,
+    #[serde(rename = "rdataA", skip_serializing_if = "Option::is_none", default)]
+    rdata_a: Option<Ipv4Addr>,
+    #[serde(rename = "rdataAAAA", skip_serializing_if = "Option::is_none", default)]
+    rdata_aaaa: Option<Ipv6Addr>,
+    #[serde(rename = "rdataNS", skip_serializing_if = "Option::is_none", default)]
+    rdata_ns: Option<String>,
+    #[serde(
+        rename = "rdataCNAME",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    rdata_cname: Option<String>,
+}
+
+impl TryFrom<&DnsRecord> for RecordJson {
+    type Error = DnsError;
+
+    fn try_from(record: &DnsRecord) -> Result<Self, Self::Error> {
+        // A `Vec` writer never compresses, so the RDATA can be lifted
+        // straight out of the encoding.
+        let mut wire = Vec::new();
+        record.write(&mut wire)?;
+
+        let mut buffer = SliceBuffer::new(&wire);
+        let mut name = DomainName::root();
+        buffer.read_qname(&mut name)?;
+        let rtype = buffer.read_u16()?;
+        let class = buffer.read_u16()?;
+        let ttl = buffer.read_u32()?;
+        let rdlength = buffer.read_u16()?;
+        let rdata = buffer.get_range(buffer.pos(), rdlength as usize)?;
+
+        let mut json = Self {
+            name: name.to_string(),
+            rtype,
+            class,
+            ttl,
+            rdlength: Some(rdlength),
+            rdata_hex: Some(encoding::to_hex(rdata)),
+            rdata_a: None,
+            rdata_aaaa: None,
+            rdata_ns: None,
+            rdata_cname: None,
+        };
+
+        match record {
+            DnsRecord::A { addr, .. } => json.rdata_a = Some(*addr),
+            DnsRecord::AAAA { addr, .. } => json.rdata_aaaa = Some(*addr),
+            DnsRecord::NS { host, .. } => json.rdata_ns = Some(host.to_string()),
+            DnsRecord::CNAME { host, .. } => json.rdata_cname = Some(host.to_string()),
+            _ => {}
+        }
+
+        Ok(json)
+    }
+}
+
+impl TryFrom<RecordJson> for DnsRecord {
+    type Error = DnsError;
+
+    fn try_from(json: RecordJson) -> Result<Self, Self::Error> {
+        let domain: DomainName = json.name.parse()?;
+        let class = DnsClass::from(json.class);
+        let ttl = json.ttl;
+
+        if let Some(hex) = &json.rdata_hex {
+            let rdata = encoding::from_hex(hex)
+                .ok_or_else(|| DnsError::InvalidJson(format!("bad RDATAHEX {hex:?}")).write())?;
+
+            return DnsRecord::from_rdata(domain, json.rtype, class, ttl, &rdata);
+        }
+
+        match (QueryType::from(json.rtype), json) {
+            (
+                QueryType::A,
+                RecordJson {
+                    rdata_a: Some(addr),
+                    ..
+                },
+            ) => Ok(DnsRecord::A {
+                domain,
+                addr,
+                class,
+                ttl,
+            }),
+            (
+                QueryType::AAAA,
+                RecordJson {
+                    rdata_aaaa: Some(addr),
+                    ..
+                },
+            ) => Ok(DnsRecord::AAAA {
+                domain,
+                addr,
+                class,
+                ttl,
+            }),
+            (
+                QueryType::NS,
+                RecordJson {
+                    rdata_ns: Some(host),
+                    ..
+                },
+            ) => Ok(DnsRecord::NS {
+                domain,
+                host: host.parse()?,
+                class,
+                ttl,
+            }),
+            (
+                QueryType::CNAME,
+                RecordJson {
+                    rdata_cname: Some(host),
+                    ..
+                },
+            ) => Ok(DnsRecord::CNAME {
+                domain,
+                host: host.parse()?,
+                class,
+                ttl,
+            }),
+            (_, json) => Err(DnsError::InvalidJson(format!(
+                "no usable RDATA for {} type {}",
+                json.name, json.rtype
+            ))
+            .write()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MessageJson {
+    #[serde(flatten)]
+    header: HeaderJson,
+    #[serde(rename = "QNAME", skip_serializing, default)]
+    qname: Option<String>,
+    #[serde(rename = "QTYPE", skip_serializing, default)]
+    qtype: Option<u16>,
+    #[serde(rename = "QCLASS", skip_serializing, default)]
+    qclass: Option<u16>,
+    #[serde(rename = "questionRRs", skip_serializing_if = "Vec::is_empty", default)]
+    questions: Vec<QuestionJson>,
+    #[serde(rename = "answerRRs", skip_serializing_if = "Vec::is_empty", default)]
+    answers: Vec<RecordJson>,
+    #[serde(
+        rename = "authorityRRs",
+        skip_serializing_if = "Vec::is_empty",
+        default
+    )]
+    authorities: Vec<RecordJson>,
+    #[serde(
+        rename = "additionalRRs",
+        skip_serializing_if = "Vec::is_empty",
+        default
+    )]
+    resources: Vec<RecordJson>,
+}
+
+fn records_to_json(records: &[DnsRecord]) -> Result<Vec<RecordJson>, DnsError> {
+    records.iter().map(RecordJson::try_from).collect()
+}
+
+fn records_from_json(records: Vec<RecordJson>) -> Result<Vec<DnsRecord>, DnsError> {
+    records.into_iter().map(DnsRecord::try_from).collect()
+}
+
+impl TryFrom<&DnsPacket> for MessageJson {
+    type Error = DnsError;
+
+    fn try_from(packet: &DnsPacket) -> Result<Self, Self::Error> {
+        // RFC 8427 has no EDNS members, so OPT goes out as a record.
+        let opt = packet
+            .edns
+            .as_ref()
+            .map(|edns| {
+                Edns {
+                    extended_rcode: packet.header.rescode.extended_bits(),
+                    ..edns.clone()
+                }
+                .to_record()
+            })
+            .transpose()?;
+
+        Ok(Self {
+            header: HeaderJson::from(&packet.header),
+            qname: None,
+            qtype: None,
+            qclass: None,
+            questions: packet.questions.iter().map(QuestionJson::from).collect(),
+            answers: records_to_json(&packet.answers)?,
+            authorities: records_to_json(&packet.authorities)?,
+            resources: records_to_json(&packet.resources)?
+                .into_iter()
+                .chain(opt.as_ref().map(RecordJson::try_from).transpose()?)
+                .collect(),
+        })
+    }
+}
+
+impl TryFrom<MessageJson> for DnsPacket {
+    type Error = DnsError;
+
+    fn try_from(json: MessageJson) -> Result<Self, Self::Error> {
+        let mut questions = json.questions;
+        // The single-question shorthand of RFC 8427 section 2.1.
+        if let (true, Some(name), Some(qtype)) = (questions.is_empty(), json.qname, json.qtype) {
+            questions.push(QuestionJson {
+                name,
+                qtype,
+                qclass: json.qclass.unwrap_or_else(class_in),
+            });
+        }
+
+        let mut packet = DnsPacket {
+            header: json.header.into(),
+            questions: questions
+                .into_iter()
+                .map(DnsQuestion::try_from)
+                .collect::<Result<_, _>>()?,
+            answers: records_from_json(json.answers)?,
+            authorities: records_from_json(json.authorities)?,
+            resources: Vec::new(),
+            edns: None,
+        };
+
+        for record in records_from_json(json.resources)? {
+            if u16::from(record.qtype()) != OPT {
+                packet.resources.push(record);
+                continue;
+            }
+            let edns = Edns::from_record(&record)
+                .filter(|_| packet.edns.is_none())
+                .ok_or_else(|| {
+                    DnsError::InvalidJson("duplicate or malformed OPT record".to_string()).write()
+                })?;
+            packet.header.rescode =
+                ResCode::from_parts(packet.header.rescode.header_bits(), edns.extended_rcode);
+            packet.edns = Some(edns);
+        }
+
+        packet.header.questions = packet.questions.len() as u16;
+        packet.header.answers = packet.answers.len() as u16;
+        packet.header.authorative_entries = packet.authorities.len() as u16;
+        packet.header.resource_entries =
+            (packet.resources.len() + packet.edns.is_some() as usize) as u16;
+
+        Ok(packet)
+    }
+}
+
+impl DnsPacket {
+    pub fn to_json(&self) -> Result<String, DnsError> {
+        serde_json::to_string(self).map_err(|err| DnsError::InvalidJson(err.to_string()).write())
+    }
+
+    /// Builds a packet from RFC 8427 JSON. Section counts are derived from
+    /// the arrays, so fixtures may leave them out.
+    pub fn from_json(json: &str) -> Result<Self, DnsError> {
+        serde_json::from_str(json).map_err(|err| DnsError::InvalidJson(err.to_string()).write())
+    }
+}
+
+impl Serialize for DnsPacket {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MessageJson::try_from(self)
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DnsPacket {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DnsPacket::try_from(MessageJson::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for DnsHeader {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        HeaderJson::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DnsHeader {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(HeaderJson::deserialize(deserializer)?.into())
+    }
+}
+
+impl Serialize for DnsQuestion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        QuestionJson::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DnsQuestion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DnsQuestion::try_from(QuestionJson::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for DnsRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RecordJson::try_from(self)
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DnsRecord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DnsRecord::try_from(RecordJson::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
@@ -1,12 +1,37 @@
-use crate::{buffer::PacketBuffer, error::DnsError};
+use std::{fmt, str::FromStr};
 
-use self::{header::DnsHeader, question::DnsQuestion, record::DnsRecord};
+use crate::{
+    buffer::{DnsRead, DnsWrite, PacketBuffer},
+    err,
+    error::{DnsError, Malformation, Section},
+};
 
+use self::{
+    edns::Edns, header::DnsHeader, name::DomainName, presentation::invalid, question::DnsQuestion,
+    record::DnsRecord,
+};
+
+pub mod dig;
+pub mod dnssec;
+pub mod edns;
+pub mod encoding;
 pub mod header;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod name;
+#[cfg(feature = "regexp")]
+pub mod naptr;
+pub mod packet_ref;
+pub mod presentation;
 pub mod question;
 pub mod record;
 pub mod response;
+pub mod serial;
+pub mod srv;
+pub mod svcb;
 
+/// Response codes from the IANA DNS RCODE registry. Values 0-15 fit the
+/// header, larger ones need the extended bits of an EDNS OPT record.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 pub enum ResCode {
     #[default]
@@ -16,29 +41,231 @@ pub enum ResCode {
     NXDOMAIN,
     NOTIMP,
     REFUSED,
+    YXDOMAIN,
+    YXRRSET,
+    NXRRSET,
+    NOTAUTH,
+    NOTZONE,
+    DSOTYPENI,
+    /// 16 is also BADSIG when it appears in a TSIG record.
+    BADVERS,
+    BADKEY,
+    BADTIME,
+    BADMODE,
+    BADNAME,
+    BADALG,
+    BADTRUNC,
+    BADCOOKIE,
+    Unknown(u16),
+}
+
+impl ResCode {
+    /// Joins the 4 bit header RCODE with the 8 bit extended RCODE carried in
+    /// the OPT TTL into the 12 bit value (RFC 6891 section 6.1.3).
+    pub fn from_parts(header: u8, extended: u8) -> Self {
+        (((extended as u16) << 4) | (header & 0x0f) as u16).into()
+    }
+
+    /// The low 4 bits, written to the header.
+    pub fn header_bits(self) -> u8 {
+        (u16::from(self) & 0x0f) as u8
+    }
+
+    /// The upper 8 bits, written to the OPT record.
+    pub fn extended_bits(self) -> u8 {
+        ((u16::from(self) >> 4) & 0xff) as u8
+    }
+
+    /// The code to send a peer that did not use EDNS. Codes above 15 do not
+    /// fit the header alone, so they become SERVFAIL.
+    pub fn without_edns(self) -> Self {
+        match self.extended_bits() {
+            0 => self,
+            _ => Self::SERVFAIL,
+        }
+    }
 }
 
 impl From<u8> for ResCode {
     fn from(value: u8) -> Self {
+        Self::from(value as u16)
+    }
+}
+
+impl From<u16> for ResCode {
+    fn from(value: u16) -> Self {
         match value {
+            0 => Self::NOERROR,
             1 => Self::FORMERR,
             2 => Self::SERVFAIL,
             3 => Self::NXDOMAIN,
             4 => Self::NOTIMP,
             5 => Self::REFUSED,
-            _ => Self::NOERROR,
+            6 => Self::YXDOMAIN,
+            7 => Self::YXRRSET,
+            8 => Self::NXRRSET,
+            9 => Self::NOTAUTH,
+            10 => Self::NOTZONE,
+            11 => Self::DSOTYPENI,
+            16 => Self::BADVERS,
+            17 => Self::BADKEY,
+            18 => Self::BADTIME,
+            19 => Self::BADMODE,
+            20 => Self::BADNAME,
+            21 => Self::BADALG,
+            22 => Self::BADTRUNC,
+            23 => Self::BADCOOKIE,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<ResCode> for u16 {
+    fn from(value: ResCode) -> Self {
+        match value {
+            ResCode::NOERROR => 0,
+            ResCode::FORMERR => 1,
+            ResCode::SERVFAIL => 2,
+            ResCode::NXDOMAIN => 3,
+            ResCode::NOTIMP => 4,
+            ResCode::REFUSED => 5,
+            ResCode::YXDOMAIN => 6,
+            ResCode::YXRRSET => 7,
+            ResCode::NXRRSET => 8,
+            ResCode::NOTAUTH => 9,
+            ResCode::NOTZONE => 10,
+            ResCode::DSOTYPENI => 11,
+            ResCode::BADVERS => 16,
+            ResCode::BADKEY => 17,
+            ResCode::BADTIME => 18,
+            ResCode::BADMODE => 19,
+            ResCode::BADNAME => 20,
+            ResCode::BADALG => 21,
+            ResCode::BADTRUNC => 22,
+            ResCode::BADCOOKIE => 23,
+            ResCode::Unknown(x) => x,
+        }
+    }
+}
+
+/// The registry mnemonic, or the number for unassigned codes.
+impl fmt::Display for ResCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NOERROR => write!(f, "NOERROR"),
+            Self::FORMERR => write!(f, "FORMERR"),
+            Self::SERVFAIL => write!(f, "SERVFAIL"),
+            Self::NXDOMAIN => write!(f, "NXDOMAIN"),
+            Self::NOTIMP => write!(f, "NOTIMP"),
+            Self::REFUSED => write!(f, "REFUSED"),
+            Self::YXDOMAIN => write!(f, "YXDOMAIN"),
+            Self::YXRRSET => write!(f, "YXRRSET"),
+            Self::NXRRSET => write!(f, "NXRRSET"),
+            Self::NOTAUTH => write!(f, "NOTAUTH"),
+            Self::NOTZONE => write!(f, "NOTZONE"),
+            Self::DSOTYPENI => write!(f, "DSOTYPENI"),
+            Self::BADVERS => write!(f, "BADVERS"),
+            Self::BADKEY => write!(f, "BADKEY"),
+            Self::BADTIME => write!(f, "BADTIME"),
+            Self::BADMODE => write!(f, "BADMODE"),
+            Self::BADNAME => write!(f, "BADNAME"),
+            Self::BADALG => write!(f, "BADALG"),
+            Self::BADTRUNC => write!(f, "BADTRUNC"),
+            Self::BADCOOKIE => write!(f, "BADCOOKIE"),
+            Self::Unknown(x) => write!(f, "{x}"),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Header OPCODE values (RFC 1035, RFC 1996 for NOTIFY, RFC 2136 for UPDATE).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Opcode {
+    #[default]
+    QUERY,
+    IQUERY,
+    STATUS,
+    NOTIFY,
+    UPDATE,
+    Unknown(u8),
+}
+
+impl From<u8> for Opcode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::QUERY,
+            1 => Self::IQUERY,
+            2 => Self::STATUS,
+            4 => Self::NOTIFY,
+            5 => Self::UPDATE,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<Opcode> for u8 {
+    fn from(value: Opcode) -> Self {
+        match value {
+            Opcode::QUERY => 0,
+            Opcode::IQUERY => 1,
+            Opcode::STATUS => 2,
+            Opcode::NOTIFY => 4,
+            Opcode::UPDATE => 5,
+            Opcode::Unknown(x) => x,
+        }
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::QUERY => write!(f, "QUERY"),
+            Self::IQUERY => write!(f, "IQUERY"),
+            Self::STATUS => write!(f, "STATUS"),
+            Self::NOTIFY => write!(f, "NOTIFY"),
+            Self::UPDATE => write!(f, "UPDATE"),
+            Self::Unknown(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum QueryType {
     Unknown(u16),
     A,
     NS,
     CNAME,
+    SOA,
+    PTR,
     MX,
+    TXT,
     AAAA,
+    SRV,
+    NAPTR,
+    DS,
+    SSHFP,
+    RRSIG,
+    NSEC,
+    DNSKEY,
+    NSEC3,
+    NSEC3PARAM,
+    TLSA,
+    SVCB,
+    HTTPS,
+    URI,
+    CAA,
+}
+
+/// Types order by number, as in NSEC type bitmaps.
+impl Ord for QueryType {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        u16::from(*self).cmp(&u16::from(*other))
+    }
+}
+
+impl PartialOrd for QueryType {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl From<QueryType> for u16 {
@@ -48,8 +275,25 @@ impl From<QueryType> for u16 {
             QueryType::A => 1,
             QueryType::NS => 2,
             QueryType::CNAME => 5,
+            QueryType::SOA => 6,
+            QueryType::PTR => 12,
             QueryType::MX => 15,
+            QueryType::TXT => 16,
             QueryType::AAAA => 28,
+            QueryType::SRV => 33,
+            QueryType::NAPTR => 35,
+            QueryType::DS => 43,
+            QueryType::SSHFP => 44,
+            QueryType::RRSIG => 46,
+            QueryType::NSEC => 47,
+            QueryType::DNSKEY => 48,
+            QueryType::NSEC3 => 50,
+            QueryType::NSEC3PARAM => 51,
+            QueryType::TLSA => 52,
+            QueryType::SVCB => 64,
+            QueryType::HTTPS => 65,
+            QueryType::URI => 256,
+            QueryType::CAA => 257,
         }
     }
 }
@@ -60,57 +304,259 @@ impl From<u16> for QueryType {
             1 => Self::A,
             2 => Self::NS,
             5 => Self::CNAME,
+            6 => Self::SOA,
+            12 => Self::PTR,
             15 => Self::MX,
+            16 => Self::TXT,
             28 => Self::AAAA,
+            33 => Self::SRV,
+            35 => Self::NAPTR,
+            43 => Self::DS,
+            44 => Self::SSHFP,
+            46 => Self::RRSIG,
+            47 => Self::NSEC,
+            48 => Self::DNSKEY,
+            50 => Self::NSEC3,
+            51 => Self::NSEC3PARAM,
+            52 => Self::TLSA,
+            64 => Self::SVCB,
+            65 => Self::HTTPS,
+            256 => Self::URI,
+            257 => Self::CAA,
             _ => Self::Unknown(value),
         }
     }
 }
 
-#[derive(Debug, Clone, Default)]
+/// The type mnemonic, or `TYPEnnn` for types without one (RFC 3597
+/// section 5).
+impl fmt::Display for QueryType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(x) => write!(f, "TYPE{x}"),
+            Self::A => write!(f, "A"),
+            Self::NS => write!(f, "NS"),
+            Self::CNAME => write!(f, "CNAME"),
+            Self::SOA => write!(f, "SOA"),
+            Self::PTR => write!(f, "PTR"),
+            Self::MX => write!(f, "MX"),
+            Self::TXT => write!(f, "TXT"),
+            Self::AAAA => write!(f, "AAAA"),
+            Self::SRV => write!(f, "SRV"),
+            Self::NAPTR => write!(f, "NAPTR"),
+            Self::DS => write!(f, "DS"),
+            Self::SSHFP => write!(f, "SSHFP"),
+            Self::RRSIG => write!(f, "RRSIG"),
+            Self::NSEC => write!(f, "NSEC"),
+            Self::DNSKEY => write!(f, "DNSKEY"),
+            Self::NSEC3 => write!(f, "NSEC3"),
+            Self::NSEC3PARAM => write!(f, "NSEC3PARAM"),
+            Self::TLSA => write!(f, "TLSA"),
+            Self::SVCB => write!(f, "SVCB"),
+            Self::HTTPS => write!(f, "HTTPS"),
+            Self::URI => write!(f, "URI"),
+            Self::CAA => write!(f, "CAA"),
+        }
+    }
+}
+
+impl QueryType {
+    /// Parses the mnemonic or `TYPEnnn` form, ignoring case.
+    pub(crate) fn from_mnemonic(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "A" => Some(Self::A),
+            "NS" => Some(Self::NS),
+            "CNAME" => Some(Self::CNAME),
+            "SOA" => Some(Self::SOA),
+            "PTR" => Some(Self::PTR),
+            "MX" => Some(Self::MX),
+            "TXT" => Some(Self::TXT),
+            "AAAA" => Some(Self::AAAA),
+            "SRV" => Some(Self::SRV),
+            "NAPTR" => Some(Self::NAPTR),
+            "DS" => Some(Self::DS),
+            "SSHFP" => Some(Self::SSHFP),
+            "RRSIG" => Some(Self::RRSIG),
+            "NSEC" => Some(Self::NSEC),
+            "DNSKEY" => Some(Self::DNSKEY),
+            "NSEC3" => Some(Self::NSEC3),
+            "NSEC3PARAM" => Some(Self::NSEC3PARAM),
+            "TLSA" => Some(Self::TLSA),
+            "SVCB" => Some(Self::SVCB),
+            "HTTPS" => Some(Self::HTTPS),
+            "URI" => Some(Self::URI),
+            "CAA" => Some(Self::CAA),
+            upper => upper
+                .strip_prefix("TYPE")
+                .and_then(|num| num.parse::<u16>().ok())
+                .map(Self::from),
+        }
+    }
+}
+
+impl FromStr for QueryType {
+    type Err = DnsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_mnemonic(s).ok_or_else(|| invalid(format!("unknown type {s:?}")))
+    }
+}
+
+/// Resource record classes (RFC 1035 3.2.4, RFC 2136 for NONE).
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+pub enum DnsClass {
+    Unknown(u16),
+    #[default]
+    IN,
+    CH,
+    HS,
+    NONE,
+    ANY,
+}
+
+impl From<DnsClass> for u16 {
+    fn from(value: DnsClass) -> Self {
+        match value {
+            DnsClass::Unknown(x) => x,
+            DnsClass::IN => 1,
+            DnsClass::CH => 3,
+            DnsClass::HS => 4,
+            DnsClass::NONE => 254,
+            DnsClass::ANY => 255,
+        }
+    }
+}
+
+impl From<u16> for DnsClass {
+    fn from(value: u16) -> Self {
+        match value {
+            1 => Self::IN,
+            3 => Self::CH,
+            4 => Self::HS,
+            254 => Self::NONE,
+            255 => Self::ANY,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+/// The class mnemonic, or `CLASSnnn` for classes without one.
+impl fmt::Display for DnsClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(x) => write!(f, "CLASS{x}"),
+            Self::IN => write!(f, "IN"),
+            Self::CH => write!(f, "CH"),
+            Self::HS => write!(f, "HS"),
+            Self::NONE => write!(f, "NONE"),
+            Self::ANY => write!(f, "ANY"),
+        }
+    }
+}
+
+impl DnsClass {
+    /// Parses the mnemonic or `CLASSnnn` form, ignoring case.
+    pub(crate) fn from_mnemonic(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "IN" => Some(Self::IN),
+            "CH" => Some(Self::CH),
+            "HS" => Some(Self::HS),
+            "NONE" => Some(Self::NONE),
+            "ANY" => Some(Self::ANY),
+            upper => upper
+                .strip_prefix("CLASS")
+                .and_then(|num| num.parse::<u16>().ok())
+                .map(Self::from),
+        }
+    }
+}
+
+impl FromStr for DnsClass {
+    type Err = DnsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_mnemonic(s).ok_or_else(|| invalid(format!("unknown class {s:?}")))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct DnsPacket {
     pub header: DnsHeader,
     pub questions: Vec<DnsQuestion>,
     pub answers: Vec<DnsRecord>,
     pub authorities: Vec<DnsRecord>,
     pub resources: Vec<DnsRecord>,
+    /// The OPT record, kept out of `resources`.
+    pub edns: Option<Edns>,
 }
 
 impl DnsPacket {
-    pub fn from_buffer(buffer: &mut PacketBuffer) -> Result<Self, DnsError> {
+    pub fn from_buffer<R: DnsRead>(buffer: &mut R) -> Result<Self, DnsError> {
         let mut res = Self::default();
-        res.header.read(buffer)?;
+        res.header
+            .read(buffer)
+            .map_err(|err| err.in_section(Section::Header))?;
 
         for _ in 0..res.header.questions {
-            let mut question = DnsQuestion::new(String::new(), QueryType::Unknown(0));
-            question.read(buffer)?;
+            let mut question = DnsQuestion::new(DomainName::root(), QueryType::Unknown(0));
+            question
+                .read(buffer)
+                .map_err(|err| err.in_section(Section::Question))?;
             res.questions.push(question);
         }
 
         for _ in 0..res.header.answers {
-            let rec = DnsRecord::read(buffer)?;
+            let rec = DnsRecord::read(buffer).map_err(|err| err.in_section(Section::Answer))?;
             res.answers.push(rec);
         }
 
         for _ in 0..res.header.authorative_entries {
-            let rec = DnsRecord::read(buffer)?;
+            let rec = DnsRecord::read(buffer).map_err(|err| err.in_section(Section::Authority))?;
             res.authorities.push(rec);
         }
 
         for _ in 0..res.header.resource_entries {
-            let rec = DnsRecord::read(buffer)?;
-            res.authorities.push(rec);
+            let pos = buffer.pos();
+            let rec = DnsRecord::read(buffer).map_err(|err| err.in_section(Section::Additional))?;
+            if u16::from(rec.qtype()) != edns::OPT {
+                res.resources.push(rec);
+                continue;
+            }
+
+            if res.edns.is_some() {
+                return Err(err!(pos => Malformation::DuplicateOpt).in_section(Section::Additional));
+            }
+            let edns = Edns::from_record(&rec).ok_or_else(|| {
+                err!(pos => Malformation::TruncatedOption).in_section(Section::Additional)
+            })?;
+            res.header.rescode =
+                ResCode::from_parts(res.header.rescode.header_bits(), edns.extended_rcode);
+            res.edns = Some(edns);
+        }
+
+        let pos = buffer.pos();
+        if pos != buffer.len() {
+            return Err(err!(pos => Malformation::TrailingData(buffer.len() - pos)));
         }
 
         Ok(res)
     }
 
-    pub fn write(&mut self, buffer: &mut PacketBuffer) -> Result<(), DnsError> {
+    pub fn write<W: DnsWrite>(&mut self, buffer: &mut W) -> Result<(), DnsError> {
         self.header.questions = self.questions.len() as u16;
         self.header.answers = self.answers.len() as u16;
         self.header.authorative_entries = self.authorities.len() as u16;
-        self.header.resource_entries = self.resources.len() as u16;
+        self.header.resource_entries = (self.resources.len() + self.edns.is_some() as usize) as u16;
+
+        let opt = match &mut self.edns {
+            Some(edns) => {
+                edns.extended_rcode = self.header.rescode.extended_bits();
+                Some(edns.to_record()?)
+            }
+            None => None,
+        };
 
+        buffer.clear_names();
         self.header.write(buffer)?;
 
         for question in &self.questions {
@@ -122,10 +568,43 @@ impl DnsPacket {
         for rec in &self.authorities {
             rec.write(buffer)?;
         }
-        for rec in &self.resources {
+        for rec in self.resources.iter().chain(&opt) {
             rec.write(buffer)?;
         }
 
         Ok(())
     }
+
+    /// Writes the packet as a UDP message of at most `limit` bytes. If it
+    /// does not fit, the records are dropped and TC is set so the client
+    /// retries over TCP (RFC 2181 section 9). The OPT record stays.
+    pub fn write_udp(&mut self, limit: usize) -> Result<PacketBuffer, DnsError> {
+        let mut buffer = PacketBuffer::new(limit);
+        self.write(&mut buffer)?;
+        if buffer.pos() > limit {
+            self.header.truncated_message = true;
+            self.answers.clear();
+            self.authorities.clear();
+            self.resources.clear();
+
+            buffer = PacketBuffer::new(limit);
+            self.write(&mut buffer)?;
+        }
+
+        buffer.truncate(buffer.pos());
+        Ok(buffer)
+    }
+
+    /// Whether this response carries the ID of `query` and echoes its
+    /// first question exactly, name case included. Checking the case
+    /// makes spoofed responses harder to get accepted (DNS 0x20).
+    pub fn echoes(&self, query: &DnsPacket) -> bool {
+        let (Some(echoed), Some(asked)) = (self.questions.first(), query.questions.first()) else {
+            return false;
+        };
+        self.header.id == query.header.id
+            && echoed.name.eq_exact(&asked.name)
+            && echoed.qtype == asked.qtype
+            && echoed.qclass == asked.qclass
+    }
 }
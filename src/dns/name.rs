@@ -0,0 +1,320 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use rand::{Rng, RngExt};
+
+use crate::{buffer::MAX_NAME_LENGTH, err, error::DnsError};
+
+pub const MAX_LABEL_LENGTH: usize = 63;
+
+/// A domain name kept as the raw labels seen on the wire.
+///
+/// Labels are arbitrary bytes, so dots, backslashes and non-ASCII octets
+/// survive a round trip. The original case is preserved, while equality,
+/// hashing and ordering ignore ASCII case as required by RFC 4343.
+#[derive(Clone, Default)]
+pub struct DomainName {
+    labels: Vec<Vec<u8>>,
+}
+
+impl DomainName {
+    /// The root name `.`.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn from_labels<I, L>(labels: I) -> Result<Self, DnsError>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Vec<u8>>,
+    {
+        let mut name = Self::root();
+        for label in labels {
+            name.push_label(label.into())?;
+        }
+        Ok(name)
+    }
+
+    /// Appends `label` below the labels already present, i.e. towards the
+    /// root.
+    pub fn push_label(&mut self, label: Vec<u8>) -> Result<(), DnsError> {
+        if label.is_empty() || label.len() > MAX_LABEL_LENGTH {
+            return Err(err!(LabelLengthExceed));
+        }
+        if self.wire_len() + 1 + label.len() > MAX_NAME_LENGTH {
+            return Err(err!(NameLengthExceed));
+        }
+
+        self.labels.push(label);
+        Ok(())
+    }
+
+    pub fn labels(&self) -> impl DoubleEndedIterator<Item = &[u8]> + ExactSizeIterator {
+        self.labels.iter().map(Vec::as_slice)
+    }
+
+    pub fn label_count(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Length of the uncompressed wire encoding, including the root label.
+    pub fn wire_len(&self) -> usize {
+        self.labels
+            .iter()
+            .map(|label| label.len() + 1)
+            .sum::<usize>()
+            + 1
+    }
+
+    /// The name with its first `n` labels removed.
+    pub fn suffix(&self, n: usize) -> Self {
+        Self {
+            labels: self.labels[n.min(self.labels.len())..].to_vec(),
+        }
+    }
+
+    /// Whether `self` is `other` or a subdomain of it.
+    pub fn ends_with(&self, other: &Self) -> bool {
+        self.labels.len() >= other.labels.len()
+            && self.suffix(self.labels.len() - other.labels.len()) == *other
+    }
+
+    pub fn to_lowercase(&self) -> Self {
+        Self {
+            labels: self
+                .labels
+                .iter()
+                .map(|label| label.to_ascii_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Copy of the name with every ASCII letter in random case, adding
+    /// entropy to upstream queries (DNS 0x20, draft-vixie-dnsext-dns0x20).
+    pub fn randomize_case<R: Rng + ?Sized>(&self, rng: &mut R) -> Self {
+        Self {
+            labels: self
+                .labels
+                .iter()
+                .map(|label| {
+                    label
+                        .iter()
+                        .map(|b| match rng.random::<bool>() {
+                            true => b.to_ascii_uppercase(),
+                            false => b.to_ascii_lowercase(),
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// The reverse lookup name of `addr`: `1.2.0.192.in-addr.arpa.` for
+    /// IPv4 (RFC 1035 section 3.5), one label per nibble under `ip6.arpa.`
+    /// for IPv6 (RFC 3596 section 2.5).
+    pub fn reverse(addr: IpAddr) -> Self {
+        let (mut labels, suffix): (Vec<Vec<u8>>, &[&[u8]]) = match addr {
+            IpAddr::V4(addr) => (
+                addr.octets()
+                    .iter()
+                    .rev()
+                    .map(|octet| octet.to_string().into_bytes())
+                    .collect(),
+                &[b"in-addr", b"arpa"],
+            ),
+            IpAddr::V6(addr) => (
+                addr.octets()
+                    .iter()
+                    .rev()
+                    .flat_map(|octet| [octet & 0x0f, octet >> 4])
+                    .map(|nibble| vec![b"0123456789abcdef"[nibble as usize]])
+                    .collect(),
+                &[b"ip6", b"arpa"],
+            ),
+        };
+        labels.extend(suffix.iter().map(|label| label.to_vec()));
+        Self { labels }
+    }
+
+    /// The address a reverse lookup name stands for, or `None` if this is
+    /// not a complete `in-addr.arpa.` or `ip6.arpa.` name.
+    pub fn reverse_addr(&self) -> Option<IpAddr> {
+        let (addr, suffix) = self.labels.split_at(self.labels.len().checked_sub(2)?);
+        let suffix: Vec<_> = suffix.iter().map(|l| l.to_ascii_lowercase()).collect();
+
+        match (suffix[0].as_slice(), suffix[1].as_slice(), addr.len()) {
+            (b"in-addr", b"arpa", 4) => {
+                let mut octets = [0u8; 4];
+                for (octet, label) in octets.iter_mut().rev().zip(addr) {
+                    if label.len() > 3 || (label.len() > 1 && label[0] == b'0') {
+                        return None;
+                    }
+                    *octet = std::str::from_utf8(label).ok()?.parse().ok()?;
+                }
+                Some(Ipv4Addr::from(octets).into())
+            }
+            (b"ip6", b"arpa", 32) => {
+                let mut octets = [0u8; 16];
+                for (i, label) in addr.iter().rev().enumerate() {
+                    let [digit] = label.as_slice() else {
+                        return None;
+                    };
+                    let nibble = (*digit as char).to_digit(16)? as u8;
+                    octets[i / 2] |= match i % 2 {
+                        0 => nibble << 4,
+                        _ => nibble,
+                    };
+                }
+                Some(Ipv6Addr::from(octets).into())
+            }
+            _ => None,
+        }
+    }
+
+    /// Case-sensitive comparison, unlike `==`.
+    pub fn eq_exact(&self, other: &Self) -> bool {
+        self.labels == other.labels
+    }
+}
+
+impl PartialEq for DomainName {
+    fn eq(&self, other: &Self) -> bool {
+        self.labels.len() == other.labels.len()
+            && self
+                .labels
+                .iter()
+                .zip(&other.labels)
+                .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+}
+
+impl Eq for DomainName {}
+
+impl Hash for DomainName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for label in &self.labels {
+            state.write_u8(label.len() as u8);
+            for b in label {
+                state.write_u8(b.to_ascii_lowercase());
+            }
+        }
+        state.write_u8(0);
+    }
+}
+
+/// Canonical DNS name order (RFC 4034 section 6.1): labels are compared
+/// right to left as lowercased octet strings.
+impl Ord for DomainName {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for (a, b) in self.labels.iter().rev().zip(other.labels.iter().rev()) {
+            let ord = a
+                .iter()
+                .map(u8::to_ascii_lowercase)
+                .cmp(b.iter().map(u8::to_ascii_lowercase));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        self.labels.len().cmp(&other.labels.len())
+    }
+}
+
+impl PartialOrd for DomainName {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Writes `label` in presentation format, escaping dots, backslashes,
+/// quotes and any byte outside printable ASCII (RFC 1035 section 5.1).
+pub(crate) fn fmt_label(f: &mut impl fmt::Write, label: &[u8]) -> fmt::Result {
+    for &b in label {
+        match b {
+            b'.' | b'\\' | b'"' | b'(' | b')' | b';' | b'@' | b'$' => write!(f, "\\{}", b as char)?,
+            0x21..=0x7e => write!(f, "{}", b as char)?,
+            _ => write!(f, "\\{b:03}")?,
+        }
+    }
+    Ok(())
+}
+
+/// Presentation format, always fully qualified with a trailing dot.
+impl fmt::Display for DomainName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_root() {
+            return write!(f, ".");
+        }
+
+        for label in &self.labels {
+            fmt_label(f, label)?;
+            write!(f, ".")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DomainName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DomainName(\"{self}\")")
+    }
+}
+
+/// Parses presentation format, honouring `\.`, `\\` and `\DDD` escapes.
+/// Names are taken as absolute whether or not they end in a dot.
+impl FromStr for DomainName {
+    type Err = DnsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = Self::root();
+        if s == "." || s.is_empty() {
+            return Ok(name);
+        }
+
+        let invalid = || DnsError::InvalidName(s.to_string()).write();
+
+        let mut label = Vec::new();
+        let mut bytes = s.bytes();
+        while let Some(b) = bytes.next() {
+            match b {
+                b'.' => {
+                    if label.is_empty() {
+                        return Err(invalid());
+                    }
+                    name.push_label(std::mem::take(&mut label))?;
+                }
+                b'\\' => match bytes.next() {
+                    Some(d) if d.is_ascii_digit() => {
+                        let digits = [Some(d), bytes.next(), bytes.next()];
+                        let mut val = 0u16;
+                        for digit in digits {
+                            match digit {
+                                Some(d) if d.is_ascii_digit() => {
+                                    val = val * 10 + (d - b'0') as u16;
+                                }
+                                _ => return Err(invalid()),
+                            }
+                        }
+                        label.push(u8::try_from(val).map_err(|_| invalid())?);
+                    }
+                    Some(c) => label.push(c),
+                    None => return Err(invalid()),
+                },
+                _ => label.push(b),
+            }
+        }
+
+        if !label.is_empty() {
+            name.push_label(label)?;
+        }
+        Ok(name)
+    }
+}
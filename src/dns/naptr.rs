@@ -0,0 +1,98 @@
+//! NAPTR substitution expressions (RFC 3402 section 3.2), the rewrite
+//! rules ENUM uses to turn a telephone number into a URI.
+
+use regex::{Captures, RegexBuilder};
+
+use crate::error::DnsError;
+
+use super::presentation::invalid;
+
+/// Splits `text` on unescaped `delim`. A backslash before the delimiter
+/// makes it literal; other escapes are kept for the next stage.
+fn split_fields(text: &str, delim: char) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == delim => field.push(next),
+                Some(next) => {
+                    field.push('\\');
+                    field.push(next);
+                }
+                None => field.push('\\'),
+            },
+            c if c == delim => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+/// Expands the replacement, where `\1` to `\9` are back-references and
+/// any other escaped character stands for itself.
+fn expand(repl: &str, captures: &Captures) -> Result<String, DnsError> {
+    let mut out = String::new();
+    let mut chars = repl.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(digit @ '1'..='9') => {
+                let group = digit as usize - '0' as usize;
+                if group >= captures.len() {
+                    return Err(invalid(format!("no group {group} in NAPTR regexp")));
+                }
+                out.push_str(captures.get(group).map_or("", |m| m.as_str()));
+            }
+            Some(other) => out.push(other),
+            None => return Err(invalid("NAPTR regexp ends in a backslash".to_string())),
+        }
+    }
+    Ok(out)
+}
+
+/// Applies the `regexp` field of a NAPTR record, such as
+/// `!^\+?(.*)$!sip:\1@example.com!`, to `input`. The first match is
+/// replaced as sed would; `None` means the expression does not match, so
+/// the record does not apply.
+///
+/// The `i` flag makes the match case-insensitive. An empty `regexp` or a
+/// malformed one is an error.
+pub fn apply_regexp(regexp: &[u8], input: &str) -> Result<Option<String>, DnsError> {
+    let text = std::str::from_utf8(regexp)
+        .map_err(|_| invalid(format!("NAPTR regexp is not UTF-8: {regexp:?}")))?;
+
+    let delim = text
+        .chars()
+        .next()
+        .filter(|&c| c != '\\' && c != 'i' && !('1'..='9').contains(&c))
+        .ok_or_else(|| invalid(format!("bad NAPTR regexp delimiter in {text:?}")))?;
+
+    let fields = split_fields(&text[delim.len_utf8()..], delim);
+    let [ere, repl, flags] = fields.as_slice() else {
+        return Err(invalid(format!(
+            "NAPTR regexp {text:?} needs three delimiters"
+        )));
+    };
+    if !matches!(flags.as_str(), "" | "i") {
+        return Err(invalid(format!("bad NAPTR regexp flags {flags:?}")));
+    }
+
+    let regex = RegexBuilder::new(ere)
+        .case_insensitive(flags == "i")
+        .build()
+        .map_err(|e| invalid(format!("bad NAPTR regexp {ere:?}: {e}")))?;
+    let Some(captures) = regex.captures(input) else {
+        return Ok(None);
+    };
+
+    let matched = captures.get(0).expect("group 0 always matches");
+    let mut out = input[..matched.start()].to_string();
+    out.push_str(&expand(repl, &captures)?);
+    out.push_str(&input[matched.end()..]);
+    Ok(Some(out))
+}
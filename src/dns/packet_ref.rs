@@ -0,0 +1,336 @@
+use std::fmt;
+
+use crate::{
+    buffer::{DnsRead, SliceBuffer},
+    err,
+    error::{DnsError, Malformation, Section},
+};
+
+use super::{
+    edns::{self, Edns},
+    header::DnsHeader,
+    name::{fmt_label, DomainName},
+    question::DnsQuestion,
+    record::DnsRecord,
+    DnsClass, DnsPacket, QueryType,
+};
+
+const HEADER_LEN: usize = 12;
+
+/// Borrowed view over a wire-format message. Only the header is decoded up
+/// front, sections are walked lazily and names are never copied unless asked
+/// for.
+#[derive(Debug, Clone)]
+pub struct DnsPacketRef<'a> {
+    data: &'a [u8],
+    pub header: DnsHeader,
+}
+
+impl<'a> DnsPacketRef<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, DnsError> {
+        let mut header = DnsHeader::default();
+        header
+            .read(&mut SliceBuffer::new(data))
+            .map_err(|err| err.in_section(Section::Header))?;
+
+        Ok(Self { data, header })
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn questions(&self) -> QuestionIter<'a> {
+        QuestionIter {
+            data: self.data,
+            pos: HEADER_LEN,
+            remaining: self.header.questions,
+        }
+    }
+
+    pub fn answers(&self) -> Result<RecordIter<'a>, DnsError> {
+        let mut questions = self.questions();
+        for question in questions.by_ref() {
+            question?;
+        }
+
+        Ok(RecordIter {
+            data: self.data,
+            pos: questions.pos,
+            remaining: self.header.answers,
+            section: Section::Answer,
+        })
+    }
+
+    pub fn authorities(&self) -> Result<RecordIter<'a>, DnsError> {
+        let pos = self.answers()?.skip_all()?;
+
+        Ok(RecordIter {
+            data: self.data,
+            pos,
+            remaining: self.header.authorative_entries,
+            section: Section::Authority,
+        })
+    }
+
+    pub fn resources(&self) -> Result<RecordIter<'a>, DnsError> {
+        let pos = self.authorities()?.skip_all()?;
+
+        Ok(RecordIter {
+            data: self.data,
+            pos,
+            remaining: self.header.resource_entries,
+            section: Section::Additional,
+        })
+    }
+
+    /// Decodes the OPT record of the additional section, if there is one.
+    pub fn edns(&self) -> Result<Option<Edns>, DnsError> {
+        let mut found = None;
+        for record in self.resources()? {
+            let record = record?;
+            if u16::from(record.qtype) != edns::OPT {
+                continue;
+            }
+            if found.is_some() {
+                return Err(err!(record.offset => Malformation::DuplicateOpt)
+                    .in_section(Section::Additional));
+            }
+            let edns = Edns::from_opt(record.class, record.ttl, record.data).ok_or_else(|| {
+                err!(record.offset => Malformation::TruncatedOption).in_section(Section::Additional)
+            })?;
+            found = Some(edns);
+        }
+        Ok(found)
+    }
+
+    /// Decodes the whole message into an owned `DnsPacket`.
+    pub fn to_packet(&self) -> Result<DnsPacket, DnsError> {
+        DnsPacket::from_buffer(&mut SliceBuffer::new(self.data))
+    }
+}
+
+/// A possibly compressed name inside a message, resolved on demand.
+#[derive(Debug, Clone, Copy)]
+pub struct NameRef<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> NameRef<'a> {
+    /// Validates the name at `offset` and returns it with the offset of the
+    /// first byte following it.
+    fn parse(data: &'a [u8], offset: usize) -> Result<(Self, usize), DnsError> {
+        let mut buffer = SliceBuffer::new(data);
+        buffer.seek(offset)?;
+        buffer.visit_qname(|_| Ok(()))?;
+
+        Ok((Self { data, offset }, buffer.pos()))
+    }
+
+    pub fn to_name(&self) -> DomainName {
+        let mut name = DomainName::root();
+        for label in self.labels() {
+            // Already validated by `parse`, so this cannot exceed any limit.
+            let _ = name.push_label(label.to_vec());
+        }
+        name
+    }
+
+    pub fn labels(&self) -> Labels<'a> {
+        Labels {
+            data: self.data,
+            pos: self.offset,
+            done: false,
+        }
+    }
+}
+
+impl fmt::Display for NameRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut root = true;
+        for label in self.labels() {
+            fmt_label(f, label)?;
+            write!(f, ".")?;
+            root = false;
+        }
+        if root {
+            write!(f, ".")?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the labels of a `NameRef`, following compression pointers.
+/// The name was validated when the `NameRef` was created.
+#[derive(Debug, Clone)]
+pub struct Labels<'a> {
+    data: &'a [u8],
+    pos: usize,
+    done: bool,
+}
+
+impl<'a> Iterator for Labels<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            let len = self.data[self.pos];
+
+            if (len & 0xc0) == 0xc0 {
+                let byte2 = self.data[self.pos + 1];
+                self.pos = (((len & 0x3f) as usize) << 8) | byte2 as usize;
+                continue;
+            }
+
+            if len == 0 {
+                self.done = true;
+                return None;
+            }
+
+            let start = self.pos + 1;
+            self.pos = start + len as usize;
+            return Some(&self.data[start..self.pos]);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DnsQuestionRef<'a> {
+    pub name: NameRef<'a>,
+    pub qtype: QueryType,
+    pub qclass: DnsClass,
+}
+
+impl DnsQuestionRef<'_> {
+    pub fn to_question(&self) -> DnsQuestion {
+        let mut question = DnsQuestion::new(self.name.to_name(), self.qtype);
+        question.qclass = self.qclass;
+        question
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QuestionIter<'a> {
+    data: &'a [u8],
+    pos: usize,
+    remaining: u16,
+}
+
+impl<'a> Iterator for QuestionIter<'a> {
+    type Item = Result<DnsQuestionRef<'a>, DnsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let res = (|| {
+            let (name, pos) = NameRef::parse(self.data, self.pos)?;
+            let mut buffer = SliceBuffer::new(self.data);
+            buffer.seek(pos)?;
+            let qtype = buffer.read_u16()?.into();
+            let qclass = buffer.read_u16()?.into();
+            self.pos = buffer.pos();
+
+            Ok(DnsQuestionRef {
+                name,
+                qtype,
+                qclass,
+            })
+        })()
+        .map_err(|err: DnsError| err.in_section(Section::Question));
+
+        if res.is_err() {
+            self.remaining = 0;
+        }
+        Some(res)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DnsRecordRef<'a> {
+    message: &'a [u8],
+    offset: usize,
+    pub domain: NameRef<'a>,
+    pub qtype: QueryType,
+    pub class: DnsClass,
+    pub ttl: u32,
+    pub data: &'a [u8],
+}
+
+impl DnsRecordRef<'_> {
+    pub fn to_record(&self) -> Result<DnsRecord, DnsError> {
+        let mut buffer = SliceBuffer::new(self.message);
+        buffer.seek(self.offset)?;
+        DnsRecord::read(&mut buffer)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordIter<'a> {
+    data: &'a [u8],
+    pos: usize,
+    remaining: u16,
+    section: Section,
+}
+
+impl RecordIter<'_> {
+    /// Consumes the section and returns the offset just past it.
+    fn skip_all(mut self) -> Result<usize, DnsError> {
+        for record in self.by_ref() {
+            record?;
+        }
+        Ok(self.pos)
+    }
+}
+
+impl<'a> Iterator for RecordIter<'a> {
+    type Item = Result<DnsRecordRef<'a>, DnsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let res = (|| {
+            let offset = self.pos;
+            let (domain, pos) = NameRef::parse(self.data, offset)?;
+            let mut buffer = SliceBuffer::new(self.data);
+            buffer.seek(pos)?;
+            let qtype = buffer.read_u16()?.into();
+            let class = buffer.read_u16()?.into();
+            let ttl = buffer.read_u32()?;
+            let data_len = buffer.read_u16()?;
+
+            let start = buffer.pos();
+            let data = self
+                .data
+                .get(start..start + data_len as usize)
+                .ok_or_else(|| err!(start => Malformation::TruncatedRdata { len: data_len }))?;
+            self.pos = start + data_len as usize;
+
+            Ok(DnsRecordRef {
+                message: self.data,
+                offset,
+                domain,
+                qtype,
+                class,
+                ttl,
+                data,
+            })
+        })()
+        .map_err(|err: DnsError| err.in_section(self.section));
+
+        if res.is_err() {
+            self.remaining = 0;
+        }
+        Some(res)
+    }
+}
@@ -0,0 +1,216 @@
+//! Helpers for reading the master file presentation format (RFC 1035
+//! section 5.1).
+
+use std::{fmt, str::FromStr};
+
+use crate::error::DnsError;
+
+use super::{encoding, name::DomainName};
+
+/// Error for RDATA that cannot be parsed or encoded.
+pub(crate) fn invalid(reason: String) -> DnsError {
+    DnsError::InvalidRecord(reason).write()
+}
+
+/// Writes a <character-string> quoted, escaping quotes, backslashes and
+/// bytes outside printable ASCII.
+pub(crate) fn fmt_character_string(f: &mut impl fmt::Write, data: &[u8]) -> fmt::Result {
+    write!(f, "\"")?;
+    for &b in data {
+        match b {
+            b'"' | b'\\' => write!(f, "\\{}", b as char)?,
+            0x20..=0x7e => write!(f, "{}", b as char)?,
+            _ => write!(f, "\\{b:03}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Resolves the escapes of a field, quoted or not, into raw bytes.
+pub(crate) fn unescape(field: &str, what: &str) -> Result<Vec<u8>, DnsError> {
+    let text = field
+        .strip_prefix('"')
+        .and_then(|quoted| quoted.strip_suffix('"'))
+        .unwrap_or(field);
+    let bad = || invalid(format!("bad {what} {field:?}"));
+
+    let mut data = Vec::with_capacity(text.len());
+    let mut bytes = text.bytes();
+    while let Some(b) = bytes.next() {
+        if b != b'\\' {
+            data.push(b);
+            continue;
+        }
+        match bytes.next() {
+            Some(d) if d.is_ascii_digit() => {
+                let mut val = (d - b'0') as u16;
+                for _ in 0..2 {
+                    match bytes.next() {
+                        Some(d) if d.is_ascii_digit() => val = val * 10 + (d - b'0') as u16,
+                        _ => return Err(bad()),
+                    }
+                }
+                data.push(u8::try_from(val).map_err(|_| bad())?);
+            }
+            Some(c) => data.push(c),
+            None => return Err(bad()),
+        }
+    }
+    Ok(data)
+}
+
+/// Splits one entry into fields. Quoted strings stay a single field with
+/// their quotes, backslash escapes are kept for the field parsers, and
+/// parentheses and `;` comments are dropped so multi-line entries work.
+pub(crate) struct Tokens<'a> {
+    fields: Vec<&'a str>,
+    next: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub(crate) fn new(text: &'a str) -> Result<Self, DnsError> {
+        let mut fields = Vec::new();
+        let mut start = None;
+        let mut quoted = false;
+        let mut escaped = false;
+        let mut comment = false;
+
+        for (i, c) in text.char_indices() {
+            if comment {
+                comment = c != '\n';
+                continue;
+            }
+            if escaped {
+                escaped = false;
+                continue;
+            }
+
+            match c {
+                '\\' => {
+                    escaped = true;
+                    start.get_or_insert(i);
+                }
+                '"' if quoted => {
+                    quoted = false;
+                    fields.extend(start.take().map(|s| &text[s..=i]));
+                }
+                _ if quoted => {}
+                '"' => {
+                    fields.extend(start.take().map(|s| &text[s..i]));
+                    quoted = true;
+                    start = Some(i);
+                }
+                ';' | '(' | ')' => {
+                    fields.extend(start.take().map(|s| &text[s..i]));
+                    comment = c == ';';
+                }
+                c if c.is_whitespace() => fields.extend(start.take().map(|s| &text[s..i])),
+                _ => {
+                    start.get_or_insert(i);
+                }
+            }
+        }
+
+        if quoted || escaped {
+            return Err(invalid(format!("unterminated field in {text:?}")));
+        }
+        fields.extend(start.map(|s| &text[s..]));
+
+        Ok(Self { fields, next: 0 })
+    }
+
+    pub(crate) fn peek(&self) -> Option<&'a str> {
+        self.fields.get(self.next).copied()
+    }
+
+    /// The next field, where `what` names it for the error message.
+    pub(crate) fn next(&mut self, what: &str) -> Result<&'a str, DnsError> {
+        let field = self
+            .peek()
+            .ok_or_else(|| invalid(format!("missing {what}")))?;
+        self.next += 1;
+        Ok(field)
+    }
+
+    pub(crate) fn parse<T: FromStr>(&mut self, what: &str) -> Result<T, DnsError> {
+        let field = self.next(what)?;
+        field
+            .parse()
+            .map_err(|_| invalid(format!("bad {what} {field:?}")))
+    }
+
+    pub(crate) fn name(&mut self, what: &str) -> Result<DomainName, DnsError> {
+        self.next(what)?.parse()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.peek().is_none()
+    }
+
+    /// A <character-string>, quoted or not, with escapes resolved.
+    pub(crate) fn character_string(&mut self, what: &str) -> Result<Vec<u8>, DnsError> {
+        let data = self.text(what)?;
+        if data.len() > 255 {
+            return Err(invalid(format!("{what} longer than 255 bytes")));
+        }
+        Ok(data)
+    }
+
+    /// Like `character_string`, without the length limit, for fields such
+    /// as the CAA value that take up the rest of the RDATA.
+    pub(crate) fn text(&mut self, what: &str) -> Result<Vec<u8>, DnsError> {
+        let field = self.next(what)?;
+        unescape(field, what)
+    }
+
+    /// All remaining fields joined, since long hex and base64 values may
+    /// be split with whitespace.
+    fn rest(&mut self, what: &str) -> Result<String, DnsError> {
+        if self.is_empty() {
+            return Err(invalid(format!("missing {what}")));
+        }
+        let rest = self.fields[self.next..].concat();
+        self.next = self.fields.len();
+        Ok(rest)
+    }
+
+    pub(crate) fn hex_rest(&mut self, what: &str) -> Result<Vec<u8>, DnsError> {
+        let hex = self.rest(what)?;
+        encoding::from_hex(&hex).ok_or_else(|| invalid(format!("bad {what} {hex:?}")))
+    }
+
+    pub(crate) fn base64_rest(&mut self, what: &str) -> Result<Vec<u8>, DnsError> {
+        let base64 = self.rest(what)?;
+        encoding::from_base64(&base64).ok_or_else(|| invalid(format!("bad {what} {base64:?}")))
+    }
+
+    /// Fails if fields are left over.
+    pub(crate) fn finish(&self) -> Result<(), DnsError> {
+        match self.peek() {
+            Some(field) => Err(invalid(format!("unexpected {field:?}"))),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads RDATA in the generic `\# <length> <hex>` form of RFC 3597
+    /// section 5, if that is what follows.
+    pub(crate) fn generic_rdata(&mut self) -> Result<Option<Vec<u8>>, DnsError> {
+        if self.peek() != Some("\\#") {
+            return Ok(None);
+        }
+        self.next += 1;
+
+        let len: usize = self.parse("RDATA length")?;
+        let data = match self.is_empty() {
+            true => Vec::new(),
+            false => self.hex_rest("RDATA hex")?,
+        };
+        if data.len() != len {
+            return Err(invalid(format!(
+                "RDATA length {len} but {} bytes given",
+                data.len()
+            )));
+        }
+        Ok(Some(data))
+    }
+}
@@ -1,31 +1,48 @@
-use crate::{buffer::PacketBuffer, error::DnsError};
+use std::fmt;
 
-use super::QueryType;
+use crate::{
+    buffer::{DnsRead, DnsWrite},
+    error::DnsError,
+};
 
-#[derive(Debug, Clone)]
+use super::{name::DomainName, DnsClass, QueryType};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct DnsQuestion {
-    pub name: String,
+    pub name: DomainName,
     pub qtype: QueryType,
+    pub qclass: DnsClass,
 }
 
 impl DnsQuestion {
-    pub fn new(name: String, qtype: QueryType) -> Self {
-        Self { name, qtype }
+    pub fn new(name: DomainName, qtype: QueryType) -> Self {
+        Self {
+            name,
+            qtype,
+            qclass: DnsClass::IN,
+        }
     }
 
-    pub fn read(&mut self, buffer: &mut PacketBuffer) -> Result<(), DnsError> {
+    pub fn read<R: DnsRead>(&mut self, buffer: &mut R) -> Result<(), DnsError> {
         buffer.read_qname(&mut self.name)?;
         self.qtype = buffer.read_u16()?.into();
-        buffer.read_u16()?;
+        self.qclass = buffer.read_u16()?.into();
 
         Ok(())
     }
 
-    pub fn write(&self, buffer: &mut PacketBuffer) -> Result<(), DnsError> {
+    pub fn write<W: DnsWrite>(&self, buffer: &mut W) -> Result<(), DnsError> {
         buffer.write_qname(&self.name)?;
 
         let type_num = self.qtype.into();
         buffer.write_u16(type_num)?;
-        buffer.write_u16(1)
+        buffer.write_u16(self.qclass.into())
+    }
+}
+
+/// `<name> <class> <type>`, as in the question section of dig output.
+impl fmt::Display for DnsQuestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.name, self.qclass, self.qtype)
     }
 }
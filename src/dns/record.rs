@@ -1,57 +1,493 @@
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::{
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
 
-use crate::{buffer::PacketBuffer, error::DnsError};
+use crate::{
+    buffer::{DnsRead, DnsWrite, SliceBuffer},
+    err,
+    error::{DnsError, Malformation},
+};
 
-use super::QueryType;
+use super::{
+    dnssec::{self, SignatureTime},
+    encoding,
+    name::DomainName,
+    presentation::{fmt_character_string, invalid, Tokens},
+    svcb::{self, SvcParam},
+    DnsClass, QueryType,
+};
+
+/// RDATA in the generic `\# <length> <hex>` presentation format of
+/// RFC 3597 section 5, used for types without a specific syntax.
+#[derive(Debug, Clone, Copy)]
+pub struct GenericRdata<'a>(pub &'a [u8]);
+
+impl fmt::Display for GenericRdata<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\\# {}", self.0.len())?;
+        if !self.0.is_empty() {
+            write!(f, " {}", encoding::to_hex(self.0))?;
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub enum DnsRecord {
+    /// A type this crate has no decoder for, or a record of any type with
+    /// empty RDATA as used by UPDATE (RFC 2136 section 2.4 and 2.5). The
+    /// RDATA is kept verbatim so it can be forwarded unchanged (RFC 3597).
     Unknown {
-        domain: String,
+        domain: DomainName,
         qtype: u16,
-        data_len: u16,
+        data: Vec<u8>,
+        class: DnsClass,
         ttl: u32,
     },
     A {
-        domain: String,
+        domain: DomainName,
         addr: Ipv4Addr,
+        class: DnsClass,
         ttl: u32,
     },
     NS {
-        domain: String,
-        host: String,
+        domain: DomainName,
+        host: DomainName,
+        class: DnsClass,
         ttl: u32,
     },
     CNAME {
-        domain: String,
-        host: String,
+        domain: DomainName,
+        host: DomainName,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// Start of a zone of authority. `serial` compares with
+    /// [`Serial`](super::serial::Serial) arithmetic.
+    SOA {
+        domain: DomainName,
+        mname: DomainName,
+        rname: DomainName,
+        serial: u32,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: u32,
+        class: DnsClass,
+        ttl: u32,
+    },
+    PTR {
+        domain: DomainName,
+        host: DomainName,
+        class: DnsClass,
         ttl: u32,
     },
     MX {
-        domain: String,
+        domain: DomainName,
         priority: u16,
-        host: String,
+        host: DomainName,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// Each <character-string> is kept as sent; SPF and DKIM values span
+    /// several of them. There is always at least one.
+    TXT {
+        domain: DomainName,
+        strings: Vec<Vec<u8>>,
+        class: DnsClass,
         ttl: u32,
     },
     AAAA {
-        domain: String,
+        domain: DomainName,
         addr: Ipv6Addr,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// Service location (RFC 2782). [`sort_targets`](super::srv::sort_targets)
+    /// gives the order clients should try targets in.
+    SRV {
+        domain: DomainName,
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: DomainName,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// Naming authority pointer (RFC 3403), as used by ENUM.
+    /// `naptr::apply_regexp` evaluates `regexp`.
+    NAPTR {
+        domain: DomainName,
+        order: u16,
+        preference: u16,
+        flags: Vec<u8>,
+        services: Vec<u8>,
+        regexp: Vec<u8>,
+        replacement: DomainName,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// Delegation signer (RFC 4034 section 5).
+    DS {
+        domain: DomainName,
+        key_tag: u16,
+        algorithm: u8,
+        digest_type: u8,
+        digest: Vec<u8>,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// SSH host key fingerprint (RFC 4255).
+    SSHFP {
+        domain: DomainName,
+        algorithm: u8,
+        fp_type: u8,
+        fingerprint: Vec<u8>,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// Signature over an RRset (RFC 4034 section 3). Times are seconds
+    /// since the epoch, compared with [`Serial`](super::serial::Serial)
+    /// arithmetic.
+    RRSIG {
+        domain: DomainName,
+        type_covered: QueryType,
+        algorithm: u8,
+        labels: u8,
+        original_ttl: u32,
+        expiration: u32,
+        inception: u32,
+        key_tag: u16,
+        signer: DomainName,
+        signature: Vec<u8>,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// Authenticated denial of existence (RFC 4034 section 4). `types`
+    /// are read in increasing order; any order is written the same.
+    /// Records with a malformed type bitmap are read as `Unknown`.
+    NSEC {
+        domain: DomainName,
+        next: DomainName,
+        types: Vec<QueryType>,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// Public key of a zone (RFC 4034 section 2), see
+    /// [`key_tag`](DnsRecord::key_tag).
+    DNSKEY {
+        domain: DomainName,
+        flags: u16,
+        protocol: u8,
+        algorithm: u8,
+        public_key: Vec<u8>,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// Hashed denial of existence (RFC 5155 section 3), with a type bitmap
+    /// handled as for `NSEC`.
+    NSEC3 {
+        domain: DomainName,
+        hash_algorithm: u8,
+        flags: u8,
+        iterations: u16,
+        salt: Vec<u8>,
+        next_hashed: Vec<u8>,
+        types: Vec<QueryType>,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// NSEC3 parameters of a zone (RFC 5155 section 4).
+    NSEC3PARAM {
+        domain: DomainName,
+        hash_algorithm: u8,
+        flags: u8,
+        iterations: u16,
+        salt: Vec<u8>,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// DANE certificate association (RFC 6698).
+    TLSA {
+        domain: DomainName,
+        usage: u8,
+        selector: u8,
+        matching_type: u8,
+        data: Vec<u8>,
+        class: DnsClass,
         ttl: u32,
     },
+    /// Service binding (RFC 9460). Records with malformed SvcParams are
+    /// read as `Unknown`.
+    SVCB {
+        domain: DomainName,
+        priority: u16,
+        target: DomainName,
+        params: Vec<SvcParam>,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// SVCB for HTTP origins, with the same RDATA.
+    HTTPS {
+        domain: DomainName,
+        priority: u16,
+        target: DomainName,
+        params: Vec<SvcParam>,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// URI for a service (RFC 7553). The target fills the rest of the
+    /// RDATA rather than being a <character-string>.
+    URI {
+        domain: DomainName,
+        priority: u16,
+        weight: u16,
+        target: Vec<u8>,
+        class: DnsClass,
+        ttl: u32,
+    },
+    /// Certification authority authorization (RFC 8659). Records whose tag
+    /// is not 1 to 15 ASCII letters and digits are read as `Unknown`.
+    CAA {
+        domain: DomainName,
+        flags: u8,
+        tag: String,
+        value: Vec<u8>,
+        class: DnsClass,
+        ttl: u32,
+    },
+}
+
+/// Whether `tag` is a well formed CAA property tag.
+fn is_caa_tag(tag: &[u8]) -> bool {
+    (1..=15).contains(&tag.len()) && tag.iter().all(u8::is_ascii_alphanumeric)
+}
+
+fn read_character_string<R: DnsRead>(buffer: &mut R) -> Result<Vec<u8>, DnsError> {
+    let len = buffer.read()? as usize;
+    let data = buffer.get_range(buffer.pos(), len)?.to_vec();
+    buffer.step(len)?;
+    Ok(data)
+}
+
+fn write_character_string<W: DnsWrite>(buffer: &mut W, data: &[u8]) -> Result<(), DnsError> {
+    let len = u8::try_from(data.len()).map_err(|_| err!(StringLengthExceed))?;
+    buffer.write(len)?;
+    for &b in data {
+        buffer.write(b)?;
+    }
+    Ok(())
+}
+
+/// Reads the bytes from the current position up to `end`, for RDATA
+/// fields that extend to the end of the record.
+fn read_rest<R: DnsRead>(buffer: &mut R, end: usize) -> Result<Vec<u8>, DnsError> {
+    let len = end.saturating_sub(buffer.pos());
+    let data = buffer.get_range(buffer.pos(), len)?.to_vec();
+    buffer.step(len)?;
+    Ok(data)
 }
 
 impl DnsRecord {
-    pub fn read(buffer: &mut PacketBuffer) -> Result<Self, DnsError> {
-        let mut domain = String::new();
+    pub fn domain(&self) -> &DomainName {
+        match self {
+            Self::Unknown { domain, .. }
+            | Self::A { domain, .. }
+            | Self::NS { domain, .. }
+            | Self::CNAME { domain, .. }
+            | Self::SOA { domain, .. }
+            | Self::PTR { domain, .. }
+            | Self::MX { domain, .. }
+            | Self::TXT { domain, .. }
+            | Self::AAAA { domain, .. }
+            | Self::SRV { domain, .. }
+            | Self::NAPTR { domain, .. }
+            | Self::DS { domain, .. }
+            | Self::SSHFP { domain, .. }
+            | Self::RRSIG { domain, .. }
+            | Self::NSEC { domain, .. }
+            | Self::DNSKEY { domain, .. }
+            | Self::NSEC3 { domain, .. }
+            | Self::NSEC3PARAM { domain, .. }
+            | Self::TLSA { domain, .. }
+            | Self::SVCB { domain, .. }
+            | Self::HTTPS { domain, .. }
+            | Self::URI { domain, .. }
+            | Self::CAA { domain, .. } => domain,
+        }
+    }
+
+    pub fn qtype(&self) -> QueryType {
+        match self {
+            Self::Unknown { qtype, .. } => QueryType::from(*qtype),
+            Self::A { .. } => QueryType::A,
+            Self::NS { .. } => QueryType::NS,
+            Self::CNAME { .. } => QueryType::CNAME,
+            Self::SOA { .. } => QueryType::SOA,
+            Self::PTR { .. } => QueryType::PTR,
+            Self::MX { .. } => QueryType::MX,
+            Self::TXT { .. } => QueryType::TXT,
+            Self::AAAA { .. } => QueryType::AAAA,
+            Self::SRV { .. } => QueryType::SRV,
+            Self::NAPTR { .. } => QueryType::NAPTR,
+            Self::DS { .. } => QueryType::DS,
+            Self::SSHFP { .. } => QueryType::SSHFP,
+            Self::RRSIG { .. } => QueryType::RRSIG,
+            Self::NSEC { .. } => QueryType::NSEC,
+            Self::DNSKEY { .. } => QueryType::DNSKEY,
+            Self::NSEC3 { .. } => QueryType::NSEC3,
+            Self::NSEC3PARAM { .. } => QueryType::NSEC3PARAM,
+            Self::TLSA { .. } => QueryType::TLSA,
+            Self::SVCB { .. } => QueryType::SVCB,
+            Self::HTTPS { .. } => QueryType::HTTPS,
+            Self::URI { .. } => QueryType::URI,
+            Self::CAA { .. } => QueryType::CAA,
+        }
+    }
+
+    pub fn class(&self) -> DnsClass {
+        match self {
+            Self::Unknown { class, .. }
+            | Self::A { class, .. }
+            | Self::NS { class, .. }
+            | Self::CNAME { class, .. }
+            | Self::SOA { class, .. }
+            | Self::PTR { class, .. }
+            | Self::MX { class, .. }
+            | Self::TXT { class, .. }
+            | Self::AAAA { class, .. }
+            | Self::SRV { class, .. }
+            | Self::NAPTR { class, .. }
+            | Self::DS { class, .. }
+            | Self::SSHFP { class, .. }
+            | Self::RRSIG { class, .. }
+            | Self::NSEC { class, .. }
+            | Self::DNSKEY { class, .. }
+            | Self::NSEC3 { class, .. }
+            | Self::NSEC3PARAM { class, .. }
+            | Self::TLSA { class, .. }
+            | Self::SVCB { class, .. }
+            | Self::HTTPS { class, .. }
+            | Self::URI { class, .. }
+            | Self::CAA { class, .. } => *class,
+        }
+    }
+
+    pub fn ttl(&self) -> u32 {
+        match self {
+            Self::Unknown { ttl, .. }
+            | Self::A { ttl, .. }
+            | Self::NS { ttl, .. }
+            | Self::CNAME { ttl, .. }
+            | Self::SOA { ttl, .. }
+            | Self::PTR { ttl, .. }
+            | Self::MX { ttl, .. }
+            | Self::TXT { ttl, .. }
+            | Self::AAAA { ttl, .. }
+            | Self::SRV { ttl, .. }
+            | Self::NAPTR { ttl, .. }
+            | Self::DS { ttl, .. }
+            | Self::SSHFP { ttl, .. }
+            | Self::RRSIG { ttl, .. }
+            | Self::NSEC { ttl, .. }
+            | Self::DNSKEY { ttl, .. }
+            | Self::NSEC3 { ttl, .. }
+            | Self::NSEC3PARAM { ttl, .. }
+            | Self::TLSA { ttl, .. }
+            | Self::SVCB { ttl, .. }
+            | Self::HTTPS { ttl, .. }
+            | Self::URI { ttl, .. }
+            | Self::CAA { ttl, .. } => *ttl,
+        }
+    }
+
+    /// The RDATA in presentation format, without owner, TTL, class or type.
+    pub fn rdata(&self) -> Rdata<'_> {
+        Rdata(self)
+    }
+
+    /// Decodes uncompressed `rdata` as the RDATA of a `rtype` record, the
+    /// way it would be read off the wire.
+    pub fn from_rdata(
+        domain: DomainName,
+        rtype: u16,
+        class: DnsClass,
+        ttl: u32,
+        rdata: &[u8],
+    ) -> Result<Self, DnsError> {
+        let rdlength = u16::try_from(rdata.len()).map_err(|_| err!(OutOfBounds))?;
+
+        let mut wire = Vec::new();
+        wire.write_qname_uncompressed(&domain)?;
+        wire.write_u16(rtype)?;
+        wire.write_u16(class.into())?;
+        wire.write_u32(ttl)?;
+        wire.write_u16(rdlength)?;
+        wire.extend_from_slice(rdata);
+
+        Self::read(&mut SliceBuffer::new(&wire))
+    }
+
+    /// Encodes the RDATA alone, uncompressed. The inverse of `from_rdata`.
+    fn to_rdata(&self) -> Result<Vec<u8>, DnsError> {
+        let mut owner = Vec::new();
+        owner.write_qname_uncompressed(self.domain())?;
+        let mut wire = Vec::new();
+        self.write(&mut wire)?;
+        // Skip the owner, type, class, TTL and RDLENGTH.
+        Ok(wire.split_off(owner.len() + 10))
+    }
+
+    /// Whether a hex, base64 or base32 field of the RDATA is empty. The
+    /// text form has no way to write that, so these records are shown as
+    /// generic RDATA.
+    fn has_empty_binary_field(&self) -> bool {
+        match self {
+            Self::SSHFP {
+                fingerprint: data, ..
+            }
+            | Self::TLSA { data, .. }
+            | Self::DS { digest: data, .. }
+            | Self::DNSKEY {
+                public_key: data, ..
+            }
+            | Self::RRSIG {
+                signature: data, ..
+            }
+            | Self::NSEC3 {
+                next_hashed: data, ..
+            } => data.is_empty(),
+            _ => false,
+        }
+    }
+
+    pub fn read<R: DnsRead>(buffer: &mut R) -> Result<Self, DnsError> {
+        let mut domain = DomainName::root();
         buffer.read_qname(&mut domain)?;
 
         let qtype_num = buffer.read_u16()?;
         let qtype = QueryType::from(qtype_num);
-        buffer.read_u16()?;
+        let class = DnsClass::from(buffer.read_u16()?);
         let ttl = buffer.read_u32()?;
         let data_len = buffer.read_u16()?;
 
-        match qtype {
+        let start = buffer.pos();
+        if start + data_len as usize > buffer.len() {
+            return Err(err!(start => Malformation::TruncatedRdata { len: data_len }));
+        }
+        if data_len == 0 {
+            return Ok(DnsRecord::Unknown {
+                domain,
+                qtype: qtype_num,
+                data: Vec::new(),
+                class,
+                ttl,
+            });
+        }
+
+        let record = match qtype {
             QueryType::A => {
                 let raw_address = buffer.read_u32()?;
                 let addr = Ipv4Addr::new(
@@ -60,7 +496,12 @@ impl DnsRecord {
                     ((raw_address >> 8) & 0xFF) as u8,
                     (raw_address & 0xFF) as u8,
                 );
-                Ok(DnsRecord::A { domain, addr, ttl })
+                DnsRecord::A {
+                    domain,
+                    addr,
+                    class,
+                    ttl,
+                }
             }
             QueryType::AAAA => {
                 let raw_addr1 = buffer.read_u32()?;
@@ -78,61 +519,354 @@ impl DnsRecord {
                     (raw_addr4 & 0xFFFF) as u16,
                 );
 
-                Ok(DnsRecord::AAAA { domain, addr, ttl })
+                DnsRecord::AAAA {
+                    domain,
+                    addr,
+                    class,
+                    ttl,
+                }
             }
             QueryType::NS => {
-                let mut ns = String::new();
+                let mut ns = DomainName::root();
                 buffer.read_qname(&mut ns)?;
 
-                Ok(DnsRecord::NS {
+                DnsRecord::NS {
                     domain,
                     host: ns,
+                    class,
                     ttl,
-                })
+                }
             }
             QueryType::CNAME => {
-                let mut cname = String::new();
+                let mut cname = DomainName::root();
                 buffer.read_qname(&mut cname)?;
 
-                Ok(DnsRecord::CNAME {
+                DnsRecord::CNAME {
                     domain,
                     host: cname,
+                    class,
                     ttl,
-                })
+                }
+            }
+            QueryType::SOA => {
+                let mut mname = DomainName::root();
+                buffer.read_qname(&mut mname)?;
+                let mut rname = DomainName::root();
+                buffer.read_qname(&mut rname)?;
+
+                DnsRecord::SOA {
+                    domain,
+                    mname,
+                    rname,
+                    serial: buffer.read_u32()?,
+                    refresh: buffer.read_u32()?,
+                    retry: buffer.read_u32()?,
+                    expire: buffer.read_u32()?,
+                    minimum: buffer.read_u32()?,
+                    class,
+                    ttl,
+                }
+            }
+            QueryType::PTR => {
+                let mut ptr = DomainName::root();
+                buffer.read_qname(&mut ptr)?;
+
+                DnsRecord::PTR {
+                    domain,
+                    host: ptr,
+                    class,
+                    ttl,
+                }
             }
             QueryType::MX => {
                 let priority = buffer.read_u16()?;
-                let mut mx = String::new();
+                let mut mx = DomainName::root();
                 buffer.read_qname(&mut mx)?;
 
-                Ok(DnsRecord::MX {
+                DnsRecord::MX {
                     domain,
                     priority,
                     host: mx,
+                    class,
+                    ttl,
+                }
+            }
+            QueryType::TXT => {
+                let mut strings = Vec::new();
+                while buffer.pos() < start + data_len as usize {
+                    strings.push(read_character_string(buffer)?);
+                }
+
+                DnsRecord::TXT {
+                    domain,
+                    strings,
+                    class,
+                    ttl,
+                }
+            }
+            QueryType::SRV => {
+                let priority = buffer.read_u16()?;
+                let weight = buffer.read_u16()?;
+                let port = buffer.read_u16()?;
+                let mut target = DomainName::root();
+                buffer.read_qname(&mut target)?;
+
+                DnsRecord::SRV {
+                    domain,
+                    priority,
+                    weight,
+                    port,
+                    target,
+                    class,
+                    ttl,
+                }
+            }
+            QueryType::NAPTR => {
+                let order = buffer.read_u16()?;
+                let preference = buffer.read_u16()?;
+                let flags = read_character_string(buffer)?;
+                let services = read_character_string(buffer)?;
+                let regexp = read_character_string(buffer)?;
+                let mut replacement = DomainName::root();
+                buffer.read_qname(&mut replacement)?;
+
+                DnsRecord::NAPTR {
+                    domain,
+                    order,
+                    preference,
+                    flags,
+                    services,
+                    regexp,
+                    replacement,
+                    class,
+                    ttl,
+                }
+            }
+            QueryType::DS => DnsRecord::DS {
+                domain,
+                key_tag: buffer.read_u16()?,
+                algorithm: buffer.read()?,
+                digest_type: buffer.read()?,
+                digest: read_rest(buffer, start + data_len as usize)?,
+                class,
+                ttl,
+            },
+            QueryType::RRSIG => {
+                let type_covered = QueryType::from(buffer.read_u16()?);
+                let algorithm = buffer.read()?;
+                let labels = buffer.read()?;
+                let original_ttl = buffer.read_u32()?;
+                let expiration = buffer.read_u32()?;
+                let inception = buffer.read_u32()?;
+                let key_tag = buffer.read_u16()?;
+                let mut signer = DomainName::root();
+                buffer.read_qname(&mut signer)?;
+
+                DnsRecord::RRSIG {
+                    domain,
+                    type_covered,
+                    algorithm,
+                    labels,
+                    original_ttl,
+                    expiration,
+                    inception,
+                    key_tag,
+                    signer,
+                    signature: read_rest(buffer, start + data_len as usize)?,
+                    class,
                     ttl,
-                })
+                }
+            }
+            QueryType::NSEC => {
+                let mut next = DomainName::root();
+                buffer.read_qname(&mut next)?;
+                let bitmap = read_rest(buffer, start + data_len as usize)?;
+
+                match dnssec::read_type_bitmap(&bitmap) {
+                    Some(types) => DnsRecord::NSEC {
+                        domain,
+                        next,
+                        types,
+                        class,
+                        ttl,
+                    },
+                    None => DnsRecord::Unknown {
+                        domain,
+                        qtype: qtype_num,
+                        data: buffer.get_range(start, data_len as usize)?.to_vec(),
+                        class,
+                        ttl,
+                    },
+                }
+            }
+            QueryType::DNSKEY => DnsRecord::DNSKEY {
+                domain,
+                flags: buffer.read_u16()?,
+                protocol: buffer.read()?,
+                algorithm: buffer.read()?,
+                public_key: read_rest(buffer, start + data_len as usize)?,
+                class,
+                ttl,
+            },
+            QueryType::NSEC3 => {
+                let hash_algorithm = buffer.read()?;
+                let flags = buffer.read()?;
+                let iterations = buffer.read_u16()?;
+                let salt = read_character_string(buffer)?;
+                let next_hashed = read_character_string(buffer)?;
+                let bitmap = read_rest(buffer, start + data_len as usize)?;
+
+                match dnssec::read_type_bitmap(&bitmap) {
+                    Some(types) => DnsRecord::NSEC3 {
+                        domain,
+                        hash_algorithm,
+                        flags,
+                        iterations,
+                        salt,
+                        next_hashed,
+                        types,
+                        class,
+                        ttl,
+                    },
+                    None => DnsRecord::Unknown {
+                        domain,
+                        qtype: qtype_num,
+                        data: buffer.get_range(start, data_len as usize)?.to_vec(),
+                        class,
+                        ttl,
+                    },
+                }
+            }
+            QueryType::NSEC3PARAM => DnsRecord::NSEC3PARAM {
+                domain,
+                hash_algorithm: buffer.read()?,
+                flags: buffer.read()?,
+                iterations: buffer.read_u16()?,
+                salt: read_character_string(buffer)?,
+                class,
+                ttl,
+            },
+            QueryType::SSHFP => DnsRecord::SSHFP {
+                domain,
+                algorithm: buffer.read()?,
+                fp_type: buffer.read()?,
+                fingerprint: read_rest(buffer, start + data_len as usize)?,
+                class,
+                ttl,
+            },
+            QueryType::TLSA => DnsRecord::TLSA {
+                domain,
+                usage: buffer.read()?,
+                selector: buffer.read()?,
+                matching_type: buffer.read()?,
+                data: read_rest(buffer, start + data_len as usize)?,
+                class,
+                ttl,
+            },
+            QueryType::SVCB | QueryType::HTTPS => {
+                let priority = buffer.read_u16()?;
+                let mut target = DomainName::root();
+                buffer.read_qname(&mut target)?;
+                let rest = read_rest(buffer, start + data_len as usize)?;
+
+                match (qtype, svcb::read_params(&rest)) {
+                    (QueryType::SVCB, Some(params)) => DnsRecord::SVCB {
+                        domain,
+                        priority,
+                        target,
+                        params,
+                        class,
+                        ttl,
+                    },
+                    (_, Some(params)) => DnsRecord::HTTPS {
+                        domain,
+                        priority,
+                        target,
+                        params,
+                        class,
+                        ttl,
+                    },
+                    (_, None) => DnsRecord::Unknown {
+                        domain,
+                        qtype: qtype_num,
+                        data: buffer.get_range(start, data_len as usize)?.to_vec(),
+                        class,
+                        ttl,
+                    },
+                }
+            }
+            QueryType::URI => DnsRecord::URI {
+                domain,
+                priority: buffer.read_u16()?,
+                weight: buffer.read_u16()?,
+                target: read_rest(buffer, start + data_len as usize)?,
+                class,
+                ttl,
+            },
+            QueryType::CAA => {
+                let flags = buffer.read()?;
+                let tag_len = buffer.read()? as usize;
+                let tag = buffer.get_range(buffer.pos(), tag_len)?.to_vec();
+                buffer.step(tag_len)?;
+                let value = read_rest(buffer, start + data_len as usize)?;
+
+                match is_caa_tag(&tag) {
+                    true => DnsRecord::CAA {
+                        domain,
+                        flags,
+                        tag: String::from_utf8(tag).expect("tag is ASCII"),
+                        value,
+                        class,
+                        ttl,
+                    },
+                    // Keep it forwardable rather than failing the message.
+                    false => DnsRecord::Unknown {
+                        domain,
+                        qtype: qtype_num,
+                        data: buffer.get_range(start, data_len as usize)?.to_vec(),
+                        class,
+                        ttl,
+                    },
+                }
             }
             QueryType::Unknown(_) => {
+                let data = buffer.get_range(start, data_len as usize)?.to_vec();
                 buffer.step(data_len as usize)?;
 
-                Ok(DnsRecord::Unknown {
+                DnsRecord::Unknown {
                     domain,
                     qtype: qtype_num,
-                    data_len,
+                    data,
+                    class,
                     ttl,
-                })
+                }
             }
+        };
+
+        let consumed = buffer.pos() - start;
+        if consumed != data_len as usize {
+            return Err(err!(start => Malformation::RdataLengthMismatch {
+                expected: data_len,
+                consumed,
+            }));
         }
+
+        Ok(record)
     }
 
-    pub fn write(&self, buffer: &mut PacketBuffer) -> Result<usize, DnsError> {
+    pub fn write<W: DnsWrite>(&self, buffer: &mut W) -> Result<usize, DnsError> {
         let start_pos = buffer.pos();
 
         match self {
-            Self::A { domain, addr, ttl } => {
+            Self::A {
+                domain,
+                addr,
+                class,
+                ttl,
+            } => {
                 buffer.write_qname(domain)?;
                 buffer.write_u16(QueryType::A.into())?;
-                buffer.write_u16(1)?;
+                buffer.write_u16((*class).into())?;
                 buffer.write_u32(*ttl)?;
                 buffer.write_u16(4)?;
 
@@ -141,10 +875,15 @@ impl DnsRecord {
                     buffer.write(oct)?;
                 }
             }
-            Self::NS { domain, host, ttl } => {
+            Self::NS {
+                domain,
+                host,
+                class,
+                ttl,
+            } => {
                 buffer.write_qname(domain)?;
                 buffer.write_u16(QueryType::NS.into())?;
-                buffer.write_u16(1)?;
+                buffer.write_u16((*class).into())?;
                 buffer.write_u32(*ttl)?;
 
                 let pos = buffer.pos();
@@ -155,10 +894,65 @@ impl DnsRecord {
                 let size = buffer.pos() - (pos + 2);
                 buffer.set_u16(pos, size as u16)?;
             }
-            Self::CNAME { domain, host, ttl } => {
+            Self::CNAME {
+                domain,
+                host,
+                class,
+                ttl,
+            } => {
                 buffer.write_qname(domain)?;
                 buffer.write_u16(QueryType::CNAME.into())?;
-                buffer.write_u16(1)?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_qname(host)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::SOA {
+                domain,
+                mname,
+                rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::SOA.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_qname(mname)?;
+                buffer.write_qname(rname)?;
+                buffer.write_u32(*serial)?;
+                buffer.write_u32(*refresh)?;
+                buffer.write_u32(*retry)?;
+                buffer.write_u32(*expire)?;
+                buffer.write_u32(*minimum)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::PTR {
+                domain,
+                host,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::PTR.into())?;
+                buffer.write_u16((*class).into())?;
                 buffer.write_u32(*ttl)?;
 
                 let pos = buffer.pos();
@@ -173,11 +967,12 @@ impl DnsRecord {
                 domain,
                 priority,
                 host,
+                class,
                 ttl,
             } => {
                 buffer.write_qname(domain)?;
                 buffer.write_u16(QueryType::MX.into())?;
-                buffer.write_u16(1)?;
+                buffer.write_u16((*class).into())?;
                 buffer.write_u32(*ttl)?;
 
                 let pos = buffer.pos();
@@ -189,10 +984,36 @@ impl DnsRecord {
                 let size = buffer.pos() - (pos + 2);
                 buffer.set_u16(pos, size as u16)?;
             }
-            Self::AAAA { domain, addr, ttl } => {
+            Self::TXT {
+                domain,
+                strings,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::TXT.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                for string in strings {
+                    write_character_string(buffer, string)?;
+                }
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::AAAA {
+                domain,
+                addr,
+                class,
+                ttl,
+            } => {
                 buffer.write_qname(domain)?;
                 buffer.write_u16(QueryType::AAAA.into())?;
-                buffer.write_u16(1)?;
+                buffer.write_u16((*class).into())?;
                 buffer.write_u32(*ttl)?;
                 buffer.write_u16(16)?;
 
@@ -200,11 +1021,886 @@ impl DnsRecord {
                     buffer.write_u16(octet)?;
                 }
             }
-            &Self::Unknown { .. } => {
-                println!("Skipping...: {:?}", self);
+            Self::SRV {
+                domain,
+                priority,
+                weight,
+                port,
+                target,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::SRV.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_u16(*priority)?;
+                buffer.write_u16(*weight)?;
+                buffer.write_u16(*port)?;
+                // RFC 2782 forbids compressing the target.
+                buffer.write_qname_uncompressed(target)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::NAPTR {
+                domain,
+                order,
+                preference,
+                flags,
+                services,
+                regexp,
+                replacement,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::NAPTR.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_u16(*order)?;
+                buffer.write_u16(*preference)?;
+                write_character_string(buffer, flags)?;
+                write_character_string(buffer, services)?;
+                write_character_string(buffer, regexp)?;
+                // RFC 3403 section 4.1 forbids compressing the replacement.
+                buffer.write_qname_uncompressed(replacement)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
             }
-        }
+            Self::DS {
+                domain,
+                key_tag,
+                algorithm,
+                digest_type,
+                digest,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::DS.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
 
-        Ok(buffer.pos() - start_pos)
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_u16(*key_tag)?;
+                buffer.write(*algorithm)?;
+                buffer.write(*digest_type)?;
+                for &b in digest {
+                    buffer.write(b)?;
+                }
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::RRSIG {
+                domain,
+                type_covered,
+                algorithm,
+                labels,
+                original_ttl,
+                expiration,
+                inception,
+                key_tag,
+                signer,
+                signature,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::RRSIG.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_u16((*type_covered).into())?;
+                buffer.write(*algorithm)?;
+                buffer.write(*labels)?;
+                buffer.write_u32(*original_ttl)?;
+                buffer.write_u32(*expiration)?;
+                buffer.write_u32(*inception)?;
+                buffer.write_u16(*key_tag)?;
+                // RFC 4034 section 3.1.7 forbids compressing the signer.
+                buffer.write_qname_uncompressed(signer)?;
+                for &b in signature {
+                    buffer.write(b)?;
+                }
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::NSEC {
+                domain,
+                next,
+                types,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::NSEC.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                // RFC 4034 section 4.1.1 forbids compressing the next name.
+                buffer.write_qname_uncompressed(next)?;
+                dnssec::write_type_bitmap(buffer, types)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::DNSKEY {
+                domain,
+                flags,
+                protocol,
+                algorithm,
+                public_key,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::DNSKEY.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_u16(*flags)?;
+                buffer.write(*protocol)?;
+                buffer.write(*algorithm)?;
+                for &b in public_key {
+                    buffer.write(b)?;
+                }
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::NSEC3 {
+                domain,
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                next_hashed,
+                types,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::NSEC3.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write(*hash_algorithm)?;
+                buffer.write(*flags)?;
+                buffer.write_u16(*iterations)?;
+                write_character_string(buffer, salt)?;
+                write_character_string(buffer, next_hashed)?;
+                dnssec::write_type_bitmap(buffer, types)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::NSEC3PARAM {
+                domain,
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::NSEC3PARAM.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write(*hash_algorithm)?;
+                buffer.write(*flags)?;
+                buffer.write_u16(*iterations)?;
+                write_character_string(buffer, salt)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::SSHFP {
+                domain,
+                algorithm,
+                fp_type,
+                fingerprint,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::SSHFP.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write(*algorithm)?;
+                buffer.write(*fp_type)?;
+                for &b in fingerprint {
+                    buffer.write(b)?;
+                }
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::TLSA {
+                domain,
+                usage,
+                selector,
+                matching_type,
+                data,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::TLSA.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write(*usage)?;
+                buffer.write(*selector)?;
+                buffer.write(*matching_type)?;
+                for &b in data {
+                    buffer.write(b)?;
+                }
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::SVCB {
+                domain,
+                priority,
+                target,
+                params,
+                class,
+                ttl,
+            }
+            | Self::HTTPS {
+                domain,
+                priority,
+                target,
+                params,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(self.qtype().into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_u16(*priority)?;
+                // RFC 9460 section 2.2 forbids compressing the target.
+                buffer.write_qname_uncompressed(target)?;
+                svcb::write_params(buffer, params)?;
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::URI {
+                domain,
+                priority,
+                weight,
+                target,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::URI.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write_u16(*priority)?;
+                buffer.write_u16(*weight)?;
+                for &b in target {
+                    buffer.write(b)?;
+                }
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::CAA {
+                domain,
+                flags,
+                tag,
+                value,
+                class,
+                ttl,
+            } => {
+                if !is_caa_tag(tag.as_bytes()) {
+                    return Err(invalid(format!("bad CAA tag {tag:?}")));
+                }
+
+                buffer.write_qname(domain)?;
+                buffer.write_u16(QueryType::CAA.into())?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+
+                let pos = buffer.pos();
+                buffer.write_u16(0)?;
+
+                buffer.write(*flags)?;
+                buffer.write(tag.len() as u8)?;
+                for &b in tag.as_bytes().iter().chain(value) {
+                    buffer.write(b)?;
+                }
+
+                let size = buffer.pos() - (pos + 2);
+                buffer.set_u16(pos, size as u16)?;
+            }
+            Self::Unknown {
+                domain,
+                qtype,
+                data,
+                class,
+                ttl,
+            } => {
+                buffer.write_qname(domain)?;
+                buffer.write_u16(*qtype)?;
+                buffer.write_u16((*class).into())?;
+                buffer.write_u32(*ttl)?;
+                buffer.write_u16(data.len() as u16)?;
+
+                for &b in data {
+                    buffer.write(b)?;
+                }
+            }
+        }
+
+        Ok(buffer.pos() - start_pos)
+    }
+}
+
+/// An NSEC3 salt in hex, or `-` when empty (RFC 5155 section 3.3).
+fn fmt_salt(f: &mut fmt::Formatter<'_>, salt: &[u8]) -> fmt::Result {
+    match salt.is_empty() {
+        true => write!(f, "-"),
+        false => write!(f, "{}", encoding::to_hex(salt)),
+    }
+}
+
+fn parse_salt(tokens: &mut Tokens) -> Result<Vec<u8>, DnsError> {
+    match tokens.next("salt")? {
+        "-" => Ok(Vec::new()),
+        hex => encoding::from_hex(hex)
+            .filter(|salt| salt.len() <= 255)
+            .ok_or_else(|| invalid(format!("bad salt {hex:?}"))),
+    }
+}
+
+fn parse_types(tokens: &mut Tokens) -> Result<Vec<QueryType>, DnsError> {
+    let mut types = Vec::new();
+    while !tokens.is_empty() {
+        types.push(tokens.parse::<QueryType>("type")?);
+    }
+    types.sort_unstable();
+    types.dedup();
+    Ok(types)
+}
+
+/// Presentation format RDATA of a record, see [`DnsRecord::rdata`].
+#[derive(Debug, Clone, Copy)]
+pub struct Rdata<'a>(&'a DnsRecord);
+
+impl fmt::Display for Rdata<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.has_empty_binary_field() {
+            let data = self.0.to_rdata().map_err(|_| fmt::Error)?;
+            return write!(f, "{}", GenericRdata(&data));
+        }
+
+        match self.0 {
+            DnsRecord::Unknown { data, .. } => write!(f, "{}", GenericRdata(data)),
+            DnsRecord::A { addr, .. } => write!(f, "{addr}"),
+            DnsRecord::NS { host, .. } => write!(f, "{host}"),
+            DnsRecord::CNAME { host, .. } => write!(f, "{host}"),
+            DnsRecord::SOA {
+                mname,
+                rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+                ..
+            } => write!(
+                f,
+                "{mname} {rname} {serial} {refresh} {retry} {expire} {minimum}"
+            ),
+            DnsRecord::PTR { host, .. } => write!(f, "{host}"),
+            DnsRecord::MX { priority, host, .. } => write!(f, "{priority} {host}"),
+            DnsRecord::TXT { strings, .. } => {
+                for (i, string) in strings.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    fmt_character_string(f, string)?;
+                }
+                Ok(())
+            }
+            DnsRecord::AAAA { addr, .. } => write!(f, "{addr}"),
+            DnsRecord::SRV {
+                priority,
+                weight,
+                port,
+                target,
+                ..
+            } => write!(f, "{priority} {weight} {port} {target}"),
+            DnsRecord::NAPTR {
+                order,
+                preference,
+                flags,
+                services,
+                regexp,
+                replacement,
+                ..
+            } => {
+                write!(f, "{order} {preference} ")?;
+                for string in [flags, services, regexp] {
+                    fmt_character_string(f, string)?;
+                    write!(f, " ")?;
+                }
+                write!(f, "{replacement}")
+            }
+            DnsRecord::DS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest,
+                ..
+            } => write!(
+                f,
+                "{key_tag} {algorithm} {digest_type} {}",
+                encoding::to_hex(digest)
+            ),
+            DnsRecord::RRSIG {
+                type_covered,
+                algorithm,
+                labels,
+                original_ttl,
+                expiration,
+                inception,
+                key_tag,
+                signer,
+                signature,
+                ..
+            } => write!(
+                f,
+                "{type_covered} {algorithm} {labels} {original_ttl} {} {} {key_tag} {signer} {}",
+                SignatureTime(*expiration),
+                SignatureTime(*inception),
+                encoding::to_base64(signature)
+            ),
+            DnsRecord::NSEC { next, types, .. } => {
+                write!(f, "{next}")?;
+                dnssec::fmt_types(f, types)
+            }
+            DnsRecord::DNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key,
+                ..
+            } => write!(
+                f,
+                "{flags} {protocol} {algorithm} {}",
+                encoding::to_base64(public_key)
+            ),
+            DnsRecord::NSEC3 {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                next_hashed,
+                types,
+                ..
+            } => {
+                write!(f, "{hash_algorithm} {flags} {iterations} ")?;
+                fmt_salt(f, salt)?;
+                write!(f, " {}", encoding::to_base32hex(next_hashed))?;
+                dnssec::fmt_types(f, types)
+            }
+            DnsRecord::NSEC3PARAM {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                ..
+            } => {
+                write!(f, "{hash_algorithm} {flags} {iterations} ")?;
+                fmt_salt(f, salt)
+            }
+            DnsRecord::SSHFP {
+                algorithm,
+                fp_type,
+                fingerprint,
+                ..
+            } => write!(f, "{algorithm} {fp_type} {}", encoding::to_hex(fingerprint)),
+            DnsRecord::TLSA {
+                usage,
+                selector,
+                matching_type,
+                data,
+                ..
+            } => write!(
+                f,
+                "{usage} {selector} {matching_type} {}",
+                encoding::to_hex(data)
+            ),
+            DnsRecord::SVCB {
+                priority,
+                target,
+                params,
+                ..
+            }
+            | DnsRecord::HTTPS {
+                priority,
+                target,
+                params,
+                ..
+            } => {
+                write!(f, "{priority} {target}")?;
+                for param in params {
+                    write!(f, " {param}")?;
+                }
+                Ok(())
+            }
+            DnsRecord::URI {
+                priority,
+                weight,
+                target,
+                ..
+            } => {
+                write!(f, "{priority} {weight} ")?;
+                fmt_character_string(f, target)
+            }
+            DnsRecord::CAA {
+                flags, tag, value, ..
+            } => {
+                write!(f, "{flags} {tag} ")?;
+                fmt_character_string(f, value)
+            }
+        }
+    }
+}
+
+/// One master file line: `<owner> <ttl> <class> <type> <rdata>`.
+impl fmt::Display for DnsRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.domain(),
+            self.ttl(),
+            self.class(),
+            self.qtype(),
+            self.rdata()
+        )
+    }
+}
+
+/// Parses a single master file entry with an absolute owner name. TTL and
+/// class may come in either order and default to 0 and IN. The RDATA of
+/// any type may also be given in the generic `\#` form.
+impl FromStr for DnsRecord {
+    type Err = DnsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+        let domain = tokens.name("owner")?;
+
+        let mut ttl = None;
+        let mut class = None;
+        let qtype = loop {
+            let field = tokens.next("type")?;
+            if ttl.is_none() && field.bytes().all(|b| b.is_ascii_digit()) {
+                ttl = Some(
+                    field
+                        .parse()
+                        .map_err(|_| invalid(format!("bad TTL {field:?}")))?,
+                );
+            } else if let (None, Some(parsed)) = (class, DnsClass::from_mnemonic(field)) {
+                class = Some(parsed);
+            } else {
+                break field.parse::<QueryType>()?;
+            }
+        };
+        let ttl = ttl.unwrap_or(0);
+        let class = class.unwrap_or_default();
+
+        if let Some(data) = tokens.generic_rdata()? {
+            return Self::from_rdata(domain, qtype.into(), class, ttl, &data);
+        }
+
+        let record = match qtype {
+            QueryType::A => DnsRecord::A {
+                domain,
+                addr: tokens.parse("IPv4 address")?,
+                class,
+                ttl,
+            },
+            QueryType::NS => DnsRecord::NS {
+                domain,
+                host: tokens.name("host")?,
+                class,
+                ttl,
+            },
+            QueryType::CNAME => DnsRecord::CNAME {
+                domain,
+                host: tokens.name("host")?,
+                class,
+                ttl,
+            },
+            QueryType::SOA => DnsRecord::SOA {
+                domain,
+                mname: tokens.name("primary server")?,
+                rname: tokens.name("mailbox")?,
+                serial: tokens.parse("serial")?,
+                refresh: tokens.parse("refresh")?,
+                retry: tokens.parse("retry")?,
+                expire: tokens.parse("expire")?,
+                minimum: tokens.parse("minimum")?,
+                class,
+                ttl,
+            },
+            QueryType::PTR => DnsRecord::PTR {
+                domain,
+                host: tokens.name("host")?,
+                class,
+                ttl,
+            },
+            QueryType::MX => DnsRecord::MX {
+                domain,
+                priority: tokens.parse("preference")?,
+                host: tokens.name("exchange")?,
+                class,
+                ttl,
+            },
+            QueryType::TXT => {
+                let mut strings = vec![tokens.character_string("text")?];
+                while !tokens.is_empty() {
+                    strings.push(tokens.character_string("text")?);
+                }
+                DnsRecord::TXT {
+                    domain,
+                    strings,
+                    class,
+                    ttl,
+                }
+            }
+            QueryType::AAAA => DnsRecord::AAAA {
+                domain,
+                addr: tokens.parse("IPv6 address")?,
+                class,
+                ttl,
+            },
+            QueryType::SRV => DnsRecord::SRV {
+                domain,
+                priority: tokens.parse("priority")?,
+                weight: tokens.parse("weight")?,
+                port: tokens.parse("port")?,
+                target: tokens.name("target")?,
+                class,
+                ttl,
+            },
+            QueryType::NAPTR => DnsRecord::NAPTR {
+                domain,
+                order: tokens.parse("order")?,
+                preference: tokens.parse("preference")?,
+                flags: tokens.character_string("flags")?,
+                services: tokens.character_string("services")?,
+                regexp: tokens.character_string("regexp")?,
+                replacement: tokens.name("replacement")?,
+                class,
+                ttl,
+            },
+            QueryType::DS => DnsRecord::DS {
+                domain,
+                key_tag: tokens.parse("key tag")?,
+                algorithm: tokens.parse("algorithm")?,
+                digest_type: tokens.parse("digest type")?,
+                digest: tokens.hex_rest("digest")?,
+                class,
+                ttl,
+            },
+            QueryType::RRSIG => {
+                let type_covered = tokens.parse("type covered")?;
+                let algorithm = tokens.parse("algorithm")?;
+                let labels = tokens.parse("labels")?;
+                let original_ttl = tokens.parse("original TTL")?;
+                let mut time = |what: &str| {
+                    let field = tokens.next(what)?;
+                    SignatureTime::parse(field)
+                        .map(|time| time.0)
+                        .ok_or_else(|| invalid(format!("bad {what} {field:?}")))
+                };
+                let expiration = time("expiration")?;
+                let inception = time("inception")?;
+
+                DnsRecord::RRSIG {
+                    domain,
+                    type_covered,
+                    algorithm,
+                    labels,
+                    original_ttl,
+                    expiration,
+                    inception,
+                    key_tag: tokens.parse("key tag")?,
+                    signer: tokens.name("signer")?,
+                    signature: tokens.base64_rest("signature")?,
+                    class,
+                    ttl,
+                }
+            }
+            QueryType::NSEC => DnsRecord::NSEC {
+                domain,
+                next: tokens.name("next domain")?,
+                types: parse_types(&mut tokens)?,
+                class,
+                ttl,
+            },
+            QueryType::DNSKEY => DnsRecord::DNSKEY {
+                domain,
+                flags: tokens.parse("flags")?,
+                protocol: tokens.parse("protocol")?,
+                algorithm: tokens.parse("algorithm")?,
+                public_key: tokens.base64_rest("public key")?,
+                class,
+                ttl,
+            },
+            QueryType::NSEC3 => {
+                let hash_algorithm = tokens.parse("hash algorithm")?;
+                let flags = tokens.parse("flags")?;
+                let iterations = tokens.parse("iterations")?;
+                let salt = parse_salt(&mut tokens)?;
+                let field = tokens.next("next hashed owner")?;
+                let next_hashed = encoding::from_base32hex(field)
+                    .filter(|hash| hash.len() <= 255)
+                    .ok_or_else(|| invalid(format!("bad next hashed owner {field:?}")))?;
+
+                DnsRecord::NSEC3 {
+                    domain,
+                    hash_algorithm,
+                    flags,
+                    iterations,
+                    salt,
+                    next_hashed,
+                    types: parse_types(&mut tokens)?,
+                    class,
+                    ttl,
+                }
+            }
+            QueryType::NSEC3PARAM => DnsRecord::NSEC3PARAM {
+                domain,
+                hash_algorithm: tokens.parse("hash algorithm")?,
+                flags: tokens.parse("flags")?,
+                iterations: tokens.parse("iterations")?,
+                salt: parse_salt(&mut tokens)?,
+                class,
+                ttl,
+            },
+            QueryType::SSHFP => DnsRecord::SSHFP {
+                domain,
+                algorithm: tokens.parse("algorithm")?,
+                fp_type: tokens.parse("fingerprint type")?,
+                fingerprint: tokens.hex_rest("fingerprint")?,
+                class,
+                ttl,
+            },
+            QueryType::TLSA => DnsRecord::TLSA {
+                domain,
+                usage: tokens.parse("certificate usage")?,
+                selector: tokens.parse("selector")?,
+                matching_type: tokens.parse("matching type")?,
+                data: tokens.hex_rest("certificate association data")?,
+                class,
+                ttl,
+            },
+            QueryType::SVCB | QueryType::HTTPS => {
+                let priority = tokens.parse("priority")?;
+                let target = tokens.name("target")?;
+                let params = svcb::parse_params(&mut tokens)?;
+                match qtype {
+                    QueryType::SVCB => DnsRecord::SVCB {
+                        domain,
+                        priority,
+                        target,
+                        params,
+                        class,
+                        ttl,
+                    },
+                    _ => DnsRecord::HTTPS {
+                        domain,
+                        priority,
+                        target,
+                        params,
+                        class,
+                        ttl,
+                    },
+                }
+            }
+            QueryType::URI => DnsRecord::URI {
+                domain,
+                priority: tokens.parse("priority")?,
+                weight: tokens.parse("weight")?,
+                target: tokens.text("target")?,
+                class,
+                ttl,
+            },
+            QueryType::CAA => {
+                let flags = tokens.parse("flags")?;
+                let tag = tokens.next("tag")?;
+                if !is_caa_tag(tag.as_bytes()) {
+                    return Err(invalid(format!("bad CAA tag {tag:?}")));
+                }
+                DnsRecord::CAA {
+                    domain,
+                    flags,
+                    tag: tag.to_owned(),
+                    value: tokens.text("value")?,
+                    class,
+                    ttl,
+                }
+            }
+            QueryType::Unknown(x) => return Err(invalid(format!("type {x} needs generic RDATA"))),
+        };
+
+        tokens.finish()?;
+        Ok(record)
     }
 }
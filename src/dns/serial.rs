@@ -0,0 +1,57 @@
+//! Serial number arithmetic (RFC 1982) for SOA serials.
+
+use std::{cmp::Ordering, fmt};
+
+/// Largest increment `checked_add` accepts, 2^31 - 1.
+const MAX_INCREMENT: u32 = (1 << 31) - 1;
+
+/// A 32 bit serial number that wraps around.
+///
+/// Comparison follows RFC 1982 section 3.2: a serial is greater than the
+/// ones up to 2^31 - 1 steps behind it. Serials exactly 2^31 apart are
+/// unordered, so this is only `PartialOrd`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Serial(pub u32);
+
+impl Serial {
+    /// Adds `n` modulo 2^32 (RFC 1982 section 3.1). Increments above
+    /// 2^31 - 1 are undefined and give `None`.
+    pub fn checked_add(self, n: u32) -> Option<Self> {
+        (n <= MAX_INCREMENT).then(|| Self(self.0.wrapping_add(n)))
+    }
+
+    /// Whether `self` is strictly newer than `other`, the check a
+    /// secondary does before transferring a zone.
+    pub fn is_newer_than(self, other: Self) -> bool {
+        self.partial_cmp(&other) == Some(Ordering::Greater)
+    }
+}
+
+impl PartialOrd for Serial {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.0.wrapping_sub(other.0) {
+            0 => Some(Ordering::Equal),
+            0x8000_0000 => None,
+            d if d < 0x8000_0000 => Some(Ordering::Greater),
+            _ => Some(Ordering::Less),
+        }
+    }
+}
+
+impl From<u32> for Serial {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Serial> for u32 {
+    fn from(value: Serial) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for Serial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
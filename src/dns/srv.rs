@@ -0,0 +1,69 @@
+//! Target selection for SRV records (RFC 2782).
+
+use rand::{Rng, RngExt};
+
+use super::record::DnsRecord;
+
+fn priority_and_weight(record: &DnsRecord) -> Option<(u16, u16)> {
+    match record {
+        DnsRecord::SRV {
+            priority, weight, ..
+        } => Some((*priority, *weight)),
+        _ => None,
+    }
+}
+
+/// Orders the SRV records among `records` in the sequence a client should
+/// try them: lowest priority first, and within a priority a weighted
+/// random shuffle where a target's chance of coming next is proportional
+/// to its weight. Other record types are left out.
+///
+/// A lone record with target `.` means the service is decidedly not
+/// available, so nothing is returned for it.
+pub fn sort_targets<'a, R: Rng + ?Sized>(
+    records: impl IntoIterator<Item = &'a DnsRecord>,
+    rng: &mut R,
+) -> Vec<&'a DnsRecord> {
+    let mut srv: Vec<(&DnsRecord, u16, u16)> = records
+        .into_iter()
+        .filter_map(|record| {
+            priority_and_weight(record).map(|(priority, weight)| (record, priority, weight))
+        })
+        .collect();
+
+    if let [(DnsRecord::SRV { target, .. }, ..)] = srv.as_slice() {
+        if target.is_root() {
+            return Vec::new();
+        }
+    }
+
+    // Zero weights go first so they still have a small chance to be
+    // picked early (RFC 2782, "Usage rules").
+    srv.sort_by_key(|&(_, priority, weight)| (priority, weight != 0));
+
+    let mut ordered = Vec::with_capacity(srv.len());
+    let mut start = 0;
+    while start < srv.len() {
+        let priority = srv[start].1;
+        let end = start + srv[start..].iter().take_while(|e| e.1 == priority).count();
+
+        let mut group = srv[start..end].to_vec();
+        while !group.is_empty() {
+            let total: u32 = group.iter().map(|&(_, _, weight)| weight as u32).sum();
+            let pick = rng.random_range(0..=total);
+
+            let mut running = 0;
+            let index = group
+                .iter()
+                .position(|&(_, _, weight)| {
+                    running += weight as u32;
+                    running >= pick
+                })
+                .unwrap_or(0);
+            ordered.push(group.remove(index).0);
+        }
+
+        start = end;
+    }
+    ordered
+}
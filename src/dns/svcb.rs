@@ -0,0 +1,411 @@
+//! Service parameters of SVCB and HTTPS records (RFC 9460).
+
+use std::{
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{buffer::DnsWrite, error::DnsError};
+
+use super::{
+    encoding,
+    presentation::{fmt_character_string, invalid, unescape, Tokens},
+};
+
+/// Key 65535 is reserved as an "invalid key".
+const INVALID_KEY: u16 = 65535;
+
+/// One SvcParam. A record keeps them in strictly increasing key order, as
+/// the wire format requires; writing a record with misordered or repeated
+/// keys fails.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum SvcParam {
+    /// Keys a client must understand to use the record, in increasing order.
+    /// Each of them must have a SvcParam of its own.
+    Mandatory(Vec<u16>),
+    /// Supported protocol IDs, such as `h2` and `h3`.
+    Alpn(Vec<Vec<u8>>),
+    NoDefaultAlpn,
+    Port(u16),
+    Ipv4Hint(Vec<Ipv4Addr>),
+    /// An ECHConfigList for TLS Encrypted Client Hello, kept opaque.
+    Ech(Vec<u8>),
+    Ipv6Hint(Vec<Ipv6Addr>),
+    Unknown {
+        key: u16,
+        value: Vec<u8>,
+    },
+}
+
+fn key_name(key: u16) -> Option<&'static str> {
+    match key {
+        0 => Some("mandatory"),
+        1 => Some("alpn"),
+        2 => Some("no-default-alpn"),
+        3 => Some("port"),
+        4 => Some("ipv4hint"),
+        5 => Some("ech"),
+        6 => Some("ipv6hint"),
+        _ => None,
+    }
+}
+
+fn fmt_key(f: &mut fmt::Formatter<'_>, key: u16) -> fmt::Result {
+    match key_name(key) {
+        Some(name) => write!(f, "{name}"),
+        None => write!(f, "key{key}"),
+    }
+}
+
+fn parse_key(text: &str) -> Option<u16> {
+    let key = match text.strip_prefix("key") {
+        Some(digits) if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) => {
+            digits.parse().ok()?
+        }
+        _ => (0..=6).find(|&key| key_name(key) == Some(text))?,
+    };
+    (key != INVALID_KEY).then_some(key)
+}
+
+/// Splits a value-list (RFC 9460 appendix A.1) on commas, where `\,` and
+/// `\\` are a literal comma and backslash inside an item.
+fn split_list(value: &[u8]) -> Vec<Vec<u8>> {
+    let mut items = vec![Vec::new()];
+    let mut bytes = value.iter();
+    while let Some(&b) = bytes.next() {
+        match b {
+            b'\\' => items.last_mut().unwrap().extend(bytes.next()),
+            b',' => items.push(Vec::new()),
+            _ => items.last_mut().unwrap().push(b),
+        }
+    }
+    items
+}
+
+/// Parses each comma separated item of a list value with `parse`.
+fn parse_list<T>(
+    value: &[u8],
+    what: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<Vec<T>, DnsError> {
+    let text = std::str::from_utf8(value).map_err(|_| invalid(format!("bad {what}")))?;
+    text.split(',')
+        .map(|item| parse(item).ok_or_else(|| invalid(format!("bad {what} {item:?}"))))
+        .collect()
+}
+
+impl SvcParam {
+    pub fn key(&self) -> u16 {
+        match self {
+            Self::Mandatory(_) => 0,
+            Self::Alpn(_) => 1,
+            Self::NoDefaultAlpn => 2,
+            Self::Port(_) => 3,
+            Self::Ipv4Hint(_) => 4,
+            Self::Ech(_) => 5,
+            Self::Ipv6Hint(_) => 6,
+            Self::Unknown { key, .. } => *key,
+        }
+    }
+
+    /// Decodes the wire value of `key`, or `None` if it is malformed.
+    fn from_wire(key: u16, value: &[u8]) -> Option<Self> {
+        let param = match key {
+            0 => {
+                let keys: Vec<u16> = value
+                    .chunks(2)
+                    .map(|pair| (pair.len() == 2).then(|| u16::from_be_bytes([pair[0], pair[1]])))
+                    .collect::<Option<_>>()?;
+                let increasing = keys.windows(2).all(|pair| pair[0] < pair[1]);
+                if keys.is_empty() || keys[0] == 0 || !increasing {
+                    return None;
+                }
+                Self::Mandatory(keys)
+            }
+            1 => {
+                let mut ids = Vec::new();
+                let mut rest = value;
+                while let [len, tail @ ..] = rest {
+                    let len = *len as usize;
+                    if len == 0 || len > tail.len() {
+                        return None;
+                    }
+                    ids.push(tail[..len].to_vec());
+                    rest = &tail[len..];
+                }
+                if ids.is_empty() {
+                    return None;
+                }
+                Self::Alpn(ids)
+            }
+            2 if value.is_empty() => Self::NoDefaultAlpn,
+            3 => Self::Port(u16::from_be_bytes(value.try_into().ok()?)),
+            4 if !value.is_empty() && value.len().is_multiple_of(4) => Self::Ipv4Hint(
+                value
+                    .chunks(4)
+                    .map(|octets| Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))
+                    .collect(),
+            ),
+            5 => Self::Ech(value.to_vec()),
+            6 if !value.is_empty() && value.len().is_multiple_of(16) => Self::Ipv6Hint(
+                value
+                    .chunks(16)
+                    .map(|octets| Ipv6Addr::from(<[u8; 16]>::try_from(octets).unwrap()))
+                    .collect(),
+            ),
+            0..=6 | INVALID_KEY => return None,
+            _ => Self::Unknown {
+                key,
+                value: value.to_vec(),
+            },
+        };
+        Some(param)
+    }
+
+    /// The wire value, checking the constraints `from_wire` enforces.
+    fn to_wire(&self) -> Result<Vec<u8>, DnsError> {
+        let mut value = Vec::new();
+        match self {
+            Self::Mandatory(keys) => {
+                let increasing = keys.windows(2).all(|pair| pair[0] < pair[1]);
+                if keys.first().is_none_or(|&key| key == 0) || !increasing {
+                    return Err(invalid(format!("bad mandatory keys {keys:?}")));
+                }
+                for key in keys {
+                    value.extend_from_slice(&key.to_be_bytes());
+                }
+            }
+            Self::Alpn(ids) => {
+                if ids.is_empty() {
+                    return Err(invalid("empty alpn".to_string()));
+                }
+                for id in ids {
+                    let len = u8::try_from(id.len())
+                        .ok()
+                        .filter(|&len| len > 0)
+                        .ok_or_else(|| invalid(format!("bad alpn id {id:?}")))?;
+                    value.push(len);
+                    value.extend_from_slice(id);
+                }
+            }
+            Self::NoDefaultAlpn => {}
+            Self::Port(port) => value.extend_from_slice(&port.to_be_bytes()),
+            Self::Ipv4Hint(addrs) => {
+                if addrs.is_empty() {
+                    return Err(invalid("empty ipv4hint".to_string()));
+                }
+                for addr in addrs {
+                    value.extend_from_slice(&addr.octets());
+                }
+            }
+            Self::Ech(config) => value.extend_from_slice(config),
+            Self::Ipv6Hint(addrs) => {
+                if addrs.is_empty() {
+                    return Err(invalid("empty ipv6hint".to_string()));
+                }
+                for addr in addrs {
+                    value.extend_from_slice(&addr.octets());
+                }
+            }
+            Self::Unknown { key, value: data } => {
+                if *key <= 6 || *key == INVALID_KEY {
+                    return Err(invalid(format!("bad unknown key {key}")));
+                }
+                value.extend_from_slice(data);
+            }
+        }
+        Ok(value)
+    }
+
+    /// Parses the presentation value of `key`, `None` when it has no `=`.
+    fn from_text(key: u16, value: Option<&[u8]>) -> Result<Self, DnsError> {
+        let name = key_name(key).map_or_else(|| format!("key{key}"), str::to_owned);
+        let Some(value) = value else {
+            return match key {
+                2 => Ok(Self::NoDefaultAlpn),
+                0..=6 => Err(invalid(format!("{name} needs a value"))),
+                _ => Ok(Self::Unknown { key, value: vec![] }),
+            };
+        };
+
+        let param = match key {
+            0 => {
+                let mut keys = parse_list(value, &name, parse_key)?;
+                keys.sort_unstable();
+                Self::Mandatory(keys)
+            }
+            1 => Self::Alpn(split_list(value)),
+            2 if value.is_empty() => Self::NoDefaultAlpn,
+            2 => return Err(invalid(format!("{name} takes no value"))),
+            3 => Self::Port(
+                std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|port| port.parse().ok())
+                    .ok_or_else(|| invalid(format!("bad {name}")))?,
+            ),
+            4 => Self::Ipv4Hint(parse_list(value, &name, |item| item.parse().ok())?),
+            5 => Self::Ech(
+                std::str::from_utf8(value)
+                    .ok()
+                    .and_then(encoding::from_base64)
+                    .ok_or_else(|| invalid(format!("bad {name}")))?,
+            ),
+            6 => Self::Ipv6Hint(parse_list(value, &name, |item| item.parse().ok())?),
+            _ => Self::Unknown {
+                key,
+                value: value.to_vec(),
+            },
+        };
+        // Catches what parsing lets through, such as empty alpn ids.
+        param.to_wire()?;
+        Ok(param)
+    }
+}
+
+impl fmt::Display for SvcParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_key(f, self.key())?;
+        match self {
+            Self::Mandatory(keys) => {
+                for (i, &key) in keys.iter().enumerate() {
+                    write!(f, "{}", if i == 0 { "=" } else { "," })?;
+                    fmt_key(f, key)?;
+                }
+                Ok(())
+            }
+            Self::Alpn(ids) => {
+                let mut list = Vec::new();
+                for (i, id) in ids.iter().enumerate() {
+                    if i > 0 {
+                        list.push(b',');
+                    }
+                    for &b in id {
+                        if b == b',' || b == b'\\' {
+                            list.push(b'\\');
+                        }
+                        list.push(b);
+                    }
+                }
+                write!(f, "=")?;
+                fmt_character_string(f, &list)
+            }
+            Self::NoDefaultAlpn => Ok(()),
+            Self::Port(port) => write!(f, "={port}"),
+            Self::Ipv4Hint(addrs) => {
+                for (i, addr) in addrs.iter().enumerate() {
+                    write!(f, "{}{addr}", if i == 0 { "=" } else { "," })?;
+                }
+                Ok(())
+            }
+            Self::Ech(config) => write!(f, "={}", encoding::to_base64(config)),
+            Self::Ipv6Hint(addrs) => {
+                for (i, addr) in addrs.iter().enumerate() {
+                    write!(f, "{}{addr}", if i == 0 { "=" } else { "," })?;
+                }
+                Ok(())
+            }
+            Self::Unknown { value, .. } if value.is_empty() => Ok(()),
+            Self::Unknown { value, .. } => {
+                write!(f, "=")?;
+                fmt_character_string(f, value)
+            }
+        }
+    }
+}
+
+/// The first key listed in a `mandatory` SvcParam that is missing from
+/// `params`, which RFC 9460 section 8 forbids.
+fn missing_mandatory_key(params: &[SvcParam]) -> Option<u16> {
+    let Some(SvcParam::Mandatory(keys)) = params.first() else {
+        return None;
+    };
+    keys.iter()
+        .copied()
+        .find(|&key| !params.iter().any(|param| param.key() == key))
+}
+
+/// Decodes the SvcParams filling the rest of the RDATA, or `None` if any
+/// is malformed, the keys are not strictly increasing or a mandatory key
+/// is missing.
+pub(crate) fn read_params(mut data: &[u8]) -> Option<Vec<SvcParam>> {
+    let mut params: Vec<SvcParam> = Vec::new();
+    while !data.is_empty() {
+        let [k0, k1, l0, l1, rest @ ..] = data else {
+            return None;
+        };
+        let key = u16::from_be_bytes([*k0, *k1]);
+        let len = u16::from_be_bytes([*l0, *l1]) as usize;
+        if len > rest.len() || params.last().is_some_and(|last| last.key() >= key) {
+            return None;
+        }
+        params.push(SvcParam::from_wire(key, &rest[..len])?);
+        data = &rest[len..];
+    }
+    if missing_mandatory_key(&params).is_some() {
+        return None;
+    }
+    Some(params)
+}
+
+pub(crate) fn write_params<W: DnsWrite>(
+    buffer: &mut W,
+    params: &[SvcParam],
+) -> Result<(), DnsError> {
+    if !params.windows(2).all(|pair| pair[0].key() < pair[1].key()) {
+        return Err(invalid("SvcParam keys out of order".to_string()));
+    }
+    if let Some(key) = missing_mandatory_key(params) {
+        return Err(invalid(format!("mandatory SvcParam key {key} is missing")));
+    }
+
+    for param in params {
+        let value = param.to_wire()?;
+        let len = u16::try_from(value.len())
+            .map_err(|_| invalid(format!("SvcParam {} too long", param.key())))?;
+        buffer.write_u16(param.key())?;
+        buffer.write_u16(len)?;
+        for b in value {
+            buffer.write(b)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses the remaining fields as SvcParams in any order and sorts them
+/// by key. Repeated keys are an error.
+pub(crate) fn parse_params(tokens: &mut Tokens) -> Result<Vec<SvcParam>, DnsError> {
+    let mut params = Vec::new();
+    while let Some(field) = tokens.peek() {
+        tokens.next("SvcParam")?;
+        let (key, value) = match field.split_once('=') {
+            // A quoted value is a field of its own; `key=` alone is empty.
+            Some((key, "")) => match tokens.peek() {
+                Some(quoted) if quoted.starts_with('"') => {
+                    tokens.next("SvcParam value")?;
+                    (key, Some(quoted))
+                }
+                _ => (key, Some("")),
+            },
+            Some((key, value)) => (key, Some(value)),
+            None => (field, None),
+        };
+
+        let key = parse_key(key).ok_or_else(|| invalid(format!("bad SvcParam key {key:?}")))?;
+        let value = value
+            .map(|value| unescape(value, "SvcParam value"))
+            .transpose()?;
+        params.push(SvcParam::from_text(key, value.as_deref())?);
+    }
+
+    params.sort_by_key(SvcParam::key);
+    if let Some(pair) = params
+        .windows(2)
+        .find(|pair| pair[0].key() == pair[1].key())
+    {
+        return Err(invalid(format!("repeated SvcParam key {}", pair[0].key())));
+    }
+    if let Some(key) = missing_mandatory_key(&params) {
+        return Err(invalid(format!("mandatory SvcParam key {key} is missing")));
+    }
+    Ok(params)
+}
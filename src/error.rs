@@ -1,27 +1,154 @@
 use core::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DnsError {
     OutOfBounds,
-    JumpsExceed,
     LabelLengthExceed,
+    NameLengthExceed,
+    StringLengthExceed,
+    InvalidName(String),
+    QuestionMismatch,
+    InvalidJson(String),
+    InvalidRecord(String),
+    InvalidCapture(String),
+    Io(String),
+    Malformed {
+        offset: usize,
+        section: Option<Section>,
+        cause: Malformation,
+    },
+}
+
+/// Message section a decode error was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Header,
+    Question,
+    Answer,
+    Authority,
+    Additional,
+}
+
+/// Why a received message was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Malformation {
+    /// The message ended before the field being read.
+    Truncated,
+    /// RDLENGTH points past the end of the message.
+    TruncatedRdata { len: u16 },
+    /// The RDATA decoder consumed a different number of bytes than RDLENGTH.
+    RdataLengthMismatch { expected: u16, consumed: usize },
+    /// A compression pointer to itself or to a later offset.
+    ForwardPointer { target: usize },
+    /// A compression pointer back into a name already being followed.
+    PointerLoop { target: usize },
+    /// Label type 0x40 or 0x80 (RFC 6891 section 5).
+    ReservedLabelType(u8),
+    /// The uncompressed name exceeds 255 bytes.
+    NameTooLong,
+    /// Bytes left over after the last section.
+    TrailingData(usize),
+    /// A second OPT record in the additional section (RFC 6891 section 6.1.1).
+    DuplicateOpt,
+    /// An EDNS option runs past the end of the OPT record.
+    TruncatedOption,
 }
 
 impl fmt::Display for DnsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::OutOfBounds => write!(f, "Buffer out of bounds, Buffer len is 512."),
-            Self::JumpsExceed => write!(f, "Limit of jumps exceeded."),
+            Self::OutOfBounds => write!(f, "Buffer out of bounds."),
             Self::LabelLengthExceed => write!(f, "Single label exceeds 63 characters of length."),
+            Self::NameLengthExceed => write!(f, "Name exceeds 255 bytes of length."),
+            Self::StringLengthExceed => {
+                write!(f, "Character-string exceeds 255 bytes of length.")
+            }
+            Self::InvalidName(name) => write!(f, "Invalid domain name: {name:?}."),
+            Self::QuestionMismatch => write!(f, "Response question does not match the query."),
+            Self::InvalidJson(reason) => write!(f, "Invalid JSON message: {reason}."),
+            Self::InvalidRecord(reason) => write!(f, "Invalid record text: {reason}."),
+            Self::InvalidCapture(reason) => write!(f, "Invalid capture file: {reason}."),
+            Self::Io(reason) => write!(f, "I/O error: {reason}."),
+            Self::Malformed {
+                offset,
+                section,
+                cause,
+            } => {
+                write!(f, "Malformed message at offset {offset}")?;
+                if let Some(section) = section {
+                    write!(f, " in {section} section")?;
+                }
+                write!(f, ": {cause}.")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Header => write!(f, "header"),
+            Self::Question => write!(f, "question"),
+            Self::Answer => write!(f, "answer"),
+            Self::Authority => write!(f, "authority"),
+            Self::Additional => write!(f, "additional"),
+        }
+    }
+}
+
+impl fmt::Display for Malformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "message truncated"),
+            Self::TruncatedRdata { len } => write!(f, "RDATA of {len} bytes runs past the end"),
+            Self::RdataLengthMismatch { expected, consumed } => write!(
+                f,
+                "RDLENGTH is {expected} but RDATA consumed {consumed} bytes"
+            ),
+            Self::ForwardPointer { target } => {
+                write!(f, "compression pointer forward to offset {target}")
+            }
+            Self::PointerLoop { target } => {
+                write!(f, "compression pointer loop through offset {target}")
+            }
+            Self::ReservedLabelType(kind) => write!(f, "reserved label type {kind:#04x}"),
+            Self::NameTooLong => write!(f, "name exceeds 255 bytes"),
+            Self::TrailingData(len) => write!(f, "{len} bytes of trailing data"),
+            Self::DuplicateOpt => write!(f, "more than one OPT record"),
+            Self::TruncatedOption => write!(f, "EDNS option runs past the end of OPT"),
         }
     }
 }
 
+impl std::error::Error for DnsError {}
+
+impl From<std::io::Error> for DnsError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err.to_string()).write()
+    }
+}
+
 impl DnsError {
     pub fn write(self) -> Self {
         println!("{}", self);
         self
     }
+
+    /// Attributes a decode error to `section` unless it already names one.
+    pub fn in_section(self, section: Section) -> Self {
+        match self {
+            Self::Malformed {
+                offset,
+                section: None,
+                cause,
+            } => Self::Malformed {
+                offset,
+                section: Some(section),
+                cause,
+            },
+            err => err,
+        }
+    }
 }
 
 #[macro_export]
@@ -29,4 +156,12 @@ macro_rules! err {
     ($err:ident) => {{
         $crate::error::DnsError::$err.write()
     }};
+    ($offset:expr => $cause:expr) => {{
+        $crate::error::DnsError::Malformed {
+            offset: $offset,
+            section: None,
+            cause: $cause,
+        }
+        .write()
+    }};
 }
@@ -0,0 +1,4 @@
+pub mod buffer;
+pub mod dns;
+pub mod error;
+pub mod pcap;
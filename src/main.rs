@@ -1,42 +1,93 @@
-use std::net::UdpSocket;
-
-use buffer::PacketBuffer;
-use dns::DnsPacket;
-use error::DnsError;
-
-use crate::dns::{question::DnsQuestion, QueryType, ResCode};
+use std::{
+    fs::File,
+    io::BufWriter,
+    net::UdpSocket,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+use dns_rs::{
+    buffer::{PacketBuffer, UDP_PACKET_SIZE},
+    dns::{
+        edns::{Edns, EDNS_PAYLOAD_SIZE},
+        packet_ref::DnsPacketRef,
+        question::DnsQuestion,
+        DnsPacket, Opcode, ResCode,
+    },
+    error::DnsError,
+    pcap::PcapWriter,
+};
+
+type PcapFile = PcapWriter<BufWriter<File>>;
+
+/// How long to wait for the upstream server's response.
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Default)]
+struct Config {
+    /// Randomize the case of upstream query names (`--0x20`) and drop
+    /// responses that do not echo it exactly, along with the query ID,
+    /// type and class, until one does or `UPSTREAM_TIMEOUT` runs out.
+    case_randomization: bool,
+    /// Save every query and response to this pcap file (`--pcap <path>`).
+    pcap: Option<PathBuf>,
+}
 
-pub mod buffer;
-pub mod dns;
-pub mod error;
+impl Config {
+    fn from_args() -> Self {
+        let mut config = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--0x20" => config.case_randomization = true,
+                "--pcap" => match args.next() {
+                    Some(path) => config.pcap = Some(path.into()),
+                    None => eprintln!("--pcap needs a file name"),
+                },
+                _ => eprintln!("Ignoring unknown argument: {arg}"),
+            }
+        }
+        config
+    }
+}
 
 fn main() -> Result<(), DnsError> {
+    let config = Config::from_args();
     let sock = UdpSocket::bind(("0.0.0.0", 2069))
         .map_err(|err| eprintln!("{err}"))
         .unwrap();
 
+    let mut capture = match &config.pcap {
+        Some(path) => Some(PcapWriter::new(BufWriter::new(File::create(path)?))?),
+        None => None,
+    };
+
     loop {
-        match handle_query(&sock) {
+        match handle_query(&sock, &config, capture.as_mut()) {
             Ok(_) => {}
             Err(e) => eprintln!("An error occured: {}", e),
         }
     }
 }
 
-fn lookup(qname: &str, qtype: QueryType) -> Result<DnsPacket, DnsError> {
+fn lookup(question: &DnsQuestion, config: &Config) -> Result<DnsPacket, DnsError> {
     let server = ("8.8.8.8", 53);
     let socket = UdpSocket::bind(("0.0.0.0", 43210))
         .map_err(|err| eprintln!("{}", err))
         .unwrap();
 
+    let mut question = question.clone();
+    if config.case_randomization {
+        question.name = question.name.randomize_case(&mut rand::rng());
+    }
+
     let mut res_packet = DnsPacket::default();
 
     res_packet.header.id = 6969;
     res_packet.header.questions = 1;
     res_packet.header.recursion_desired = true;
-    res_packet
-        .questions
-        .push(DnsQuestion::new(qname.to_string(), qtype));
+    res_packet.questions.push(question.clone());
+    res_packet.edns = Some(Edns::default());
 
     let mut req_buffer = PacketBuffer::default();
     res_packet.write(&mut req_buffer)?;
@@ -46,24 +97,47 @@ fn lookup(qname: &str, qtype: QueryType) -> Result<DnsPacket, DnsError> {
         .map_err(|err| eprintln!("{err}"))
         .unwrap();
 
-    let mut res_buffer = PacketBuffer::default();
-    socket
-        .recv_from(&mut res_buffer.buf)
-        .map_err(|err| eprintln!("{err}"))
-        .unwrap();
+    // With 0x20, anything that does not echo the query is dropped and we
+    // keep waiting, so a spoofed or stale datagram cannot stand in for the
+    // real response.
+    let deadline = Instant::now() + UPSTREAM_TIMEOUT;
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if timeout.is_zero() {
+            return Err(DnsError::Io("upstream timed out".to_string()).write());
+        }
+        socket.set_read_timeout(Some(timeout))?;
+
+        let mut res_buffer = PacketBuffer::new(EDNS_PAYLOAD_SIZE as usize);
+        let (len, _) = socket.recv_from(&mut res_buffer.buf)?;
+        res_buffer.truncate(len);
 
-    DnsPacket::from_buffer(&mut res_buffer)
+        match DnsPacket::from_buffer(&mut res_buffer) {
+            Ok(res) if !config.case_randomization || res.echoes(&res_packet) => return Ok(res),
+            Ok(_) => {
+                DnsError::QuestionMismatch.write();
+            }
+            Err(_) if config.case_randomization => {}
+            Err(err) => return Err(err),
+        }
+    }
 }
 
-fn handle_query(socket: &UdpSocket) -> Result<(), DnsError> {
-    let mut req_buffer = PacketBuffer::default();
+fn handle_query(
+    socket: &UdpSocket,
+    config: &Config,
+    capture: Option<&mut PcapFile>,
+) -> Result<(), DnsError> {
+    let mut req_buffer = PacketBuffer::new(EDNS_PAYLOAD_SIZE as usize);
 
-    let (_len, src) = socket
+    let (len, src) = socket
         .recv_from(&mut req_buffer.buf)
         .map_err(|err| eprintln!("{err}"))
         .unwrap();
+    req_buffer.truncate(len);
 
-    let mut request = DnsPacket::from_buffer(&mut req_buffer)?;
+    let request = DnsPacketRef::new(&req_buffer.buf)?;
+    let request_edns = request.edns()?;
 
     let mut packet = DnsPacket::default();
     packet.header.id = request.header.id;
@@ -71,43 +145,72 @@ fn handle_query(socket: &UdpSocket) -> Result<(), DnsError> {
     packet.header.recursion_available = true;
     packet.header.response = true;
 
-    if let Some(question) = request.questions.pop() {
-        println!("Received query: {:?}", question);
+    packet.header.opcode = request.header.opcode;
+    // Answer EDNS with EDNS, echoing DO (RFC 3225 section 3).
+    packet.edns = request_edns.as_ref().map(|edns| Edns {
+        dnssec_ok: edns.dnssec_ok,
+        ..Edns::default()
+    });
 
-        match lookup(&question.name, question.qtype) {
-            Ok(res) => {
-                packet.questions.push(question);
-                packet.header.rescode = res.header.rescode;
-
-                for rec in res.answers {
-                    println!("Answer: {:?}", rec);
-                    packet.answers.push(rec);
-                }
-                for rec in res.authorities {
-                    println!("Authority: {:?}", rec);
-                    packet.authorities.push(rec);
-                }
-                for rec in res.resources {
-                    println!("Resource: {:?}", rec);
-                    packet.resources.push(rec);
-                }
-            }
-            Err(_) => packet.header.rescode = ResCode::SERVFAIL,
+    match request.header.opcode {
+        _ if request_edns.as_ref().is_some_and(|edns| edns.version > 0) => {
+            packet.header.rescode = ResCode::BADVERS;
+        }
+        Opcode::QUERY => forward_query(&request, &mut packet, config)?,
+        opcode => {
+            println!("Unsupported opcode: {:?}", opcode);
+            packet.header.rescode = ResCode::NOTIMP;
         }
-    } else {
-        packet.header.rescode = ResCode::FORMERR;
     }
 
-    let mut res_buffer = PacketBuffer::default();
-    packet.write(&mut res_buffer)?;
+    let limit = request_edns
+        .as_ref()
+        .map_or(UDP_PACKET_SIZE, Edns::response_size);
+    let res_buffer = packet.write_udp(limit)?;
+    println!("{}", packet.dig());
 
-    let len = res_buffer.pos();
-    let data = res_buffer.get_range(0, len)?;
+    let data = res_buffer.buf.as_slice();
 
     socket
         .send_to(data, src)
         .map_err(|err| eprintln!("{err}"))
         .unwrap();
 
+    if let Some(capture) = capture {
+        let local = socket.local_addr()?;
+        capture.write_message(SystemTime::now(), src, local, &req_buffer.buf)?;
+        capture.write_message(SystemTime::now(), local, src, data)?;
+        capture.flush()?;
+    }
+
+    Ok(())
+}
+
+fn forward_query(
+    request: &DnsPacketRef,
+    packet: &mut DnsPacket,
+    config: &Config,
+) -> Result<(), DnsError> {
+    if let Some(question) = request.questions().next().transpose()? {
+        let question = question.to_question();
+        println!("Received query: {}", question);
+
+        match lookup(&question, config) {
+            Ok(res) => {
+                packet.questions.push(question);
+                packet.header.rescode = match packet.edns {
+                    Some(_) => res.header.rescode,
+                    None => res.header.rescode.without_edns(),
+                };
+                packet.answers = res.answers;
+                packet.authorities = res.authorities;
+                packet.resources = res.resources;
+            }
+            Err(_) => packet.header.rescode = ResCode::SERVFAIL,
+        }
+    } else {
+        packet.header.rescode = ResCode::FORMERR;
+    }
+
     Ok(())
 }
@@ -0,0 +1,616 @@
+//! Reading DNS messages out of pcap and pcapng captures, and writing
+//! messages into pcap files that Wireshark can open.
+//!
+//! Only what is needed to find DNS payloads is decoded: Ethernet (with
+//! VLAN tags), Linux cooked (SLL and SLL2), BSD loopback and raw IP link
+//! layers, IPv4 and IPv6, then UDP and TCP. TCP streams are not
+//! reassembled, so only messages whose length prefix and body sit in a
+//! single segment are found. IP fragments are skipped.
+
+use std::{
+    io::Write,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    buffer::{PacketBuffer, SliceBuffer},
+    dns::DnsPacket,
+    error::DnsError,
+};
+
+pub const DNS_PORT: u16 = 53;
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const PCAPNG_SHB: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER: u32 = 0x1a2b_3c4d;
+const PCAPNG_IDB: u32 = 1;
+const PCAPNG_SPB: u32 = 3;
+const PCAPNG_EPB: u32 = 6;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+/// Older captures write raw IP under its platform DLT_RAW value instead of
+/// LINKTYPE_RAW: 12 on most systems, 14 on OpenBSD.
+const DLT_RAW: u32 = 12;
+const DLT_RAW_OPENBSD: u32 = 14;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88a8;
+
+const IPPROTO_TCP: u8 = 6;
+const IPPROTO_UDP: u8 = 17;
+
+fn invalid(reason: impl Into<String>) -> DnsError {
+    DnsError::InvalidCapture(reason.into()).write()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Udp,
+    Tcp,
+}
+
+/// One DNS message found in a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    /// Time since the Unix epoch. Zero for pcapng simple packet blocks,
+    /// which carry none.
+    pub timestamp: Duration,
+    pub src: SocketAddr,
+    pub dst: SocketAddr,
+    pub transport: Transport,
+    /// The DNS message, without the TCP length prefix.
+    pub payload: Vec<u8>,
+}
+
+impl Capture {
+    pub fn decode(&self) -> Result<DnsPacket, DnsError> {
+        DnsPacket::from_buffer(&mut SliceBuffer::new(&self.payload))
+    }
+}
+
+/// Finds the DNS messages to or from port 53 in a pcap or pcapng file.
+pub fn read(data: &[u8]) -> Result<Vec<Capture>, DnsError> {
+    read_with_ports(data, &[DNS_PORT])
+}
+
+/// Like [`read`], for DNS served on other ports.
+pub fn read_with_ports(data: &[u8], ports: &[u16]) -> Result<Vec<Capture>, DnsError> {
+    let mut frames = Vec::new();
+    match data
+        .get(..4)
+        .map(|magic| u32::from_le_bytes(magic.try_into().unwrap()))
+    {
+        Some(PCAPNG_SHB) => read_pcapng(data, &mut frames)?,
+        Some(_) => read_pcap(data, &mut frames)?,
+        None => return Err(invalid("file too short")),
+    }
+
+    let mut captures = Vec::new();
+    for frame in frames {
+        extract(&frame, ports, &mut captures);
+    }
+    Ok(captures)
+}
+
+/// A link layer frame as stored in the file.
+struct Frame<'a> {
+    timestamp: Duration,
+    linktype: u32,
+    data: &'a [u8],
+}
+
+/// Bounds-checked reads in the byte order of the file.
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8], big_endian: bool) -> Self {
+        Self {
+            data,
+            pos: 0,
+            big_endian,
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], DnsError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos.saturating_add(len))
+            .ok_or_else(|| invalid(format!("truncated at offset {}", self.pos)))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, DnsError> {
+        let bytes = self.bytes(2)?.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(&mut self) -> Result<u32, DnsError> {
+        let bytes = self.bytes(4)?.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+fn read_pcap<'a>(data: &'a [u8], frames: &mut Vec<Frame<'a>>) -> Result<(), DnsError> {
+    let magic = u32::from_le_bytes(data[..4].try_into().unwrap());
+    let (big_endian, nanos) = match magic {
+        PCAP_MAGIC_MICROS => (false, false),
+        PCAP_MAGIC_NANOS => (false, true),
+        m if m.swap_bytes() == PCAP_MAGIC_MICROS => (true, false),
+        m if m.swap_bytes() == PCAP_MAGIC_NANOS => (true, true),
+        m => return Err(invalid(format!("unknown magic {m:#010x}"))),
+    };
+
+    let mut cursor = Cursor::new(data, big_endian);
+    cursor.bytes(20)?;
+    // The upper bits of the link type field hold FCS information.
+    let linktype = cursor.u32()? & 0x0fff_ffff;
+
+    while !cursor.is_empty() {
+        let secs = cursor.u32()?;
+        let frac = cursor.u32()?;
+        let captured = cursor.u32()?;
+        let _original = cursor.u32()?;
+        let data = cursor.bytes(captured as usize)?;
+
+        let frac = match nanos {
+            true => Duration::from_nanos(frac as u64),
+            false => Duration::from_micros(frac as u64),
+        };
+        frames.push(Frame {
+            timestamp: Duration::from_secs(secs as u64) + frac,
+            linktype,
+            data,
+        });
+    }
+    Ok(())
+}
+
+/// An interface from a pcapng Interface Description Block.
+struct Interface {
+    linktype: u32,
+    /// Timestamp units per second (`if_tsresol`).
+    units: u64,
+}
+
+fn read_pcapng<'a>(data: &'a [u8], frames: &mut Vec<Frame<'a>>) -> Result<(), DnsError> {
+    let mut pos = 0;
+    let mut big_endian = false;
+    let mut interfaces: Vec<Interface> = Vec::new();
+
+    while pos < data.len() {
+        let rest = &data[pos..];
+        let block_type = Cursor::new(rest, big_endian).u32()?;
+
+        if block_type == PCAPNG_SHB {
+            // Each section may use its own byte order.
+            let magic = Cursor::new(rest, false).bytes(12)?;
+            big_endian = match u32::from_le_bytes(magic[8..12].try_into().unwrap()) {
+                PCAPNG_BYTE_ORDER => false,
+                m if m.swap_bytes() == PCAPNG_BYTE_ORDER => true,
+                m => return Err(invalid(format!("unknown byte order magic {m:#010x}"))),
+            };
+            interfaces.clear();
+        }
+
+        let mut cursor = Cursor::new(rest, big_endian);
+        cursor.u32()?;
+        let len = cursor.u32()? as usize;
+        if len < 12 || !len.is_multiple_of(4) {
+            return Err(invalid(format!("bad block length {len} at offset {pos}")));
+        }
+        let body = cursor.bytes(len - 12)?;
+        let mut body = Cursor::new(body, big_endian);
+
+        match block_type {
+            PCAPNG_IDB => {
+                let linktype = body.u16()? as u32;
+                body.bytes(6)?;
+                interfaces.push(Interface {
+                    linktype,
+                    units: tsresol(&mut body)?,
+                });
+            }
+            PCAPNG_EPB => {
+                let interface = body.u32()? as usize;
+                let high = body.u32()? as u64;
+                let low = body.u32()? as u64;
+                let captured = body.u32()? as usize;
+                let _original = body.u32()?;
+                let data = body.bytes(captured)?;
+
+                let interface = interfaces
+                    .get(interface)
+                    .ok_or_else(|| invalid(format!("unknown interface {interface}")))?;
+                let ticks = (high << 32) | low;
+                frames.push(Frame {
+                    timestamp: Duration::from_nanos(
+                        (ticks as u128 * 1_000_000_000 / interface.units as u128) as u64,
+                    ),
+                    linktype: interface.linktype,
+                    data,
+                });
+            }
+            PCAPNG_SPB => {
+                let original = body.u32()? as usize;
+                let data = body.bytes(original.min(len - 16))?;
+                let interface = interfaces
+                    .first()
+                    .ok_or_else(|| invalid("simple packet block before any interface"))?;
+                frames.push(Frame {
+                    timestamp: Duration::ZERO,
+                    linktype: interface.linktype,
+                    data,
+                });
+            }
+            _ => {}
+        }
+
+        pos += len;
+    }
+    Ok(())
+}
+
+/// Reads the IDB options for `if_tsresol`, defaulting to microseconds.
+fn tsresol(options: &mut Cursor) -> Result<u64, DnsError> {
+    while !options.is_empty() {
+        let code = options.u16()?;
+        let len = options.u16()? as usize;
+        let value = options.bytes(len)?;
+        options.bytes((4 - len % 4) % 4)?;
+
+        match (code, value) {
+            (0, _) => break,
+            (9, [resol]) => {
+                let exp = (resol & 0x7f) as u32;
+                return match resol & 0x80 {
+                    0 => 10u64.checked_pow(exp),
+                    _ => 2u64.checked_pow(exp),
+                }
+                .ok_or_else(|| invalid(format!("timestamp resolution {resol:#04x}")));
+            }
+            _ => {}
+        }
+    }
+    Ok(1_000_000)
+}
+
+/// Strips the link layer, returning the IP version and packet.
+fn network_layer<'a>(linktype: u32, data: &'a [u8]) -> Option<(u8, &'a [u8])> {
+    let ethertype = |ethertype: u16, payload: &'a [u8]| match ethertype {
+        ETHERTYPE_IPV4 => Some((4, payload)),
+        ETHERTYPE_IPV6 => Some((6, payload)),
+        _ => None,
+    };
+
+    match linktype {
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            loop {
+                let kind = u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?);
+                match kind {
+                    ETHERTYPE_VLAN | ETHERTYPE_QINQ => offset += 4,
+                    _ => return ethertype(kind, data.get(offset + 2..)?),
+                }
+            }
+        }
+        LINKTYPE_LINUX_SLL => ethertype(
+            u16::from_be_bytes(data.get(14..16)?.try_into().ok()?),
+            data.get(16..)?,
+        ),
+        LINKTYPE_LINUX_SLL2 => ethertype(
+            u16::from_be_bytes(data.get(0..2)?.try_into().ok()?),
+            data.get(20..)?,
+        ),
+        LINKTYPE_NULL => {
+            // The address family is in the byte order of the capturing host.
+            let family = data.get(..4)?;
+            let family = u32::from_le_bytes(family.try_into().ok()?)
+                .min(u32::from_be_bytes(family.try_into().ok()?));
+            match family {
+                2 => Some((4, data.get(4..)?)),
+                24 | 28 | 30 => Some((6, data.get(4..)?)),
+                _ => None,
+            }
+        }
+        LINKTYPE_RAW | DLT_RAW | DLT_RAW_OPENBSD => Some((data.first()? >> 4, data)),
+        LINKTYPE_IPV4 => Some((4, data)),
+        LINKTYPE_IPV6 => Some((6, data)),
+        _ => None,
+    }
+}
+
+/// Strips the IP header, returning addresses, protocol and payload.
+fn transport_layer(version: u8, data: &[u8]) -> Option<(IpAddr, IpAddr, u8, &[u8])> {
+    match version {
+        4 => {
+            let header_len = (*data.first()? & 0x0f) as usize * 4;
+            let total_len = u16::from_be_bytes(data.get(2..4)?.try_into().ok()?) as usize;
+            let fragment = u16::from_be_bytes(data.get(6..8)?.try_into().ok()?);
+            if fragment & 0x3fff != 0 {
+                return None;
+            }
+            let src: [u8; 4] = data.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = data.get(16..20)?.try_into().ok()?;
+            Some((
+                src.into(),
+                dst.into(),
+                data[9],
+                data.get(header_len..total_len.max(header_len).min(data.len()))?,
+            ))
+        }
+        6 => {
+            let payload_len = u16::from_be_bytes(data.get(4..6)?.try_into().ok()?) as usize;
+            let src: [u8; 16] = data.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = data.get(24..40)?.try_into().ok()?;
+            let mut next = *data.get(6)?;
+            let mut payload = data.get(40..(40 + payload_len).min(data.len()))?;
+
+            // Hop-by-hop, routing and destination options headers.
+            while matches!(next, 0 | 43 | 60) {
+                next = *payload.first()?;
+                let len = (*payload.get(1)? as usize + 1) * 8;
+                payload = payload.get(len..)?;
+            }
+            Some((src.into(), dst.into(), next, payload))
+        }
+        _ => None,
+    }
+}
+
+fn extract(frame: &Frame, ports: &[u16], captures: &mut Vec<Capture>) {
+    let Some((version, packet)) = network_layer(frame.linktype, frame.data) else {
+        return;
+    };
+    let Some((src_ip, dst_ip, protocol, segment)) = transport_layer(version, packet) else {
+        return;
+    };
+    let Some(header) = segment.get(..4) else {
+        return;
+    };
+    let src_port = u16::from_be_bytes([header[0], header[1]]);
+    let dst_port = u16::from_be_bytes([header[2], header[3]]);
+    if !ports.contains(&src_port) && !ports.contains(&dst_port) {
+        return;
+    }
+
+    let capture = |transport, payload: &[u8]| Capture {
+        timestamp: frame.timestamp,
+        src: SocketAddr::new(src_ip, src_port),
+        dst: SocketAddr::new(dst_ip, dst_port),
+        transport,
+        payload: payload.to_vec(),
+    };
+
+    match protocol {
+        IPPROTO_UDP => {
+            if let Some(payload) = segment.get(8..) {
+                captures.push(capture(Transport::Udp, payload));
+            }
+        }
+        IPPROTO_TCP => {
+            let Some(&offset) = segment.get(12) else {
+                return;
+            };
+            let Some(mut stream) = segment.get((offset >> 4) as usize * 4..) else {
+                return;
+            };
+            // Messages are prefixed with a two byte length (RFC 1035
+            // section 4.2.2).
+            while let Some(len) = stream.get(..2) {
+                let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+                let Some(message) = stream.get(2..2 + len) else {
+                    break;
+                };
+                captures.push(capture(Transport::Tcp, message));
+                stream = &stream[2 + len..];
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Writes DNS messages into a pcap file as raw IP packets with synthetic
+/// IPv4 or IPv6 and UDP headers.
+pub struct PcapWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Writes the file header.
+    pub fn new(mut inner: W) -> Result<Self, DnsError> {
+        inner.write_all(&PCAP_MAGIC_MICROS.to_le_bytes())?;
+        inner.write_all(&2u16.to_le_bytes())?;
+        inner.write_all(&4u16.to_le_bytes())?;
+        inner.write_all(&0i32.to_le_bytes())?;
+        inner.write_all(&0u32.to_le_bytes())?;
+        inner.write_all(&65535u32.to_le_bytes())?;
+        inner.write_all(&LINKTYPE_RAW.to_le_bytes())?;
+        Ok(Self { inner })
+    }
+
+    /// Encodes `packet` and writes it as a datagram from `src` to `dst`.
+    pub fn write_packet(
+        &mut self,
+        timestamp: SystemTime,
+        src: SocketAddr,
+        dst: SocketAddr,
+        packet: &mut DnsPacket,
+    ) -> Result<(), DnsError> {
+        let mut buffer = PacketBuffer::new(0);
+        packet.write(&mut buffer)?;
+        self.write_message(timestamp, src, dst, &buffer.buf)
+    }
+
+    /// Writes an already encoded message as a datagram from `src` to `dst`.
+    pub fn write_message(
+        &mut self,
+        timestamp: SystemTime,
+        src: SocketAddr,
+        dst: SocketAddr,
+        message: &[u8],
+    ) -> Result<(), DnsError> {
+        let frame = match (src.ip(), dst.ip()) {
+            (IpAddr::V4(src_ip), IpAddr::V4(dst_ip)) => {
+                ipv4_udp(src_ip, dst_ip, src.port(), dst.port(), message)?
+            }
+            (src_ip, dst_ip) => ipv6_udp(
+                to_ipv6(src_ip),
+                to_ipv6(dst_ip),
+                src.port(),
+                dst.port(),
+                message,
+            )?,
+        };
+
+        let since_epoch = timestamp.duration_since(UNIX_EPOCH).unwrap_or_default();
+        self.inner
+            .write_all(&(since_epoch.as_secs() as u32).to_le_bytes())?;
+        self.inner
+            .write_all(&since_epoch.subsec_micros().to_le_bytes())?;
+        self.inner.write_all(&(frame.len() as u32).to_le_bytes())?;
+        self.inner.write_all(&(frame.len() as u32).to_le_bytes())?;
+        self.inner.write_all(&frame)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), DnsError> {
+        Ok(self.inner.flush()?)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+fn to_ipv6(ip: IpAddr) -> Ipv6Addr {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    }
+}
+
+/// The ones' complement sum used by IP, UDP and TCP checksums.
+fn checksum(parts: &[&[u8]]) -> u16 {
+    let mut sum = 0u32;
+    for part in parts {
+        for pair in part.chunks(2) {
+            let word = match pair {
+                [a, b] => u16::from_be_bytes([*a, *b]),
+                [a] => u16::from_be_bytes([*a, 0]),
+                _ => unreachable!(),
+            };
+            sum += word as u32;
+        }
+    }
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+fn udp(
+    pseudo_header: &[u8],
+    src_port: u16,
+    dst_port: u16,
+    message: &[u8],
+) -> Result<Vec<u8>, DnsError> {
+    let len = u16::try_from(8 + message.len())
+        .map_err(|_| invalid(format!("message of {} bytes", message.len())))?;
+
+    let mut segment = Vec::with_capacity(len as usize);
+    segment.extend_from_slice(&src_port.to_be_bytes());
+    segment.extend_from_slice(&dst_port.to_be_bytes());
+    segment.extend_from_slice(&len.to_be_bytes());
+    segment.extend_from_slice(&[0, 0]);
+    segment.extend_from_slice(message);
+
+    // The pseudo header has an even length, so the odd byte padding in
+    // `checksum` only ever applies to the end of the segment.
+    let sum = match checksum(&[pseudo_header, &segment]) {
+        0 => 0xffff,
+        sum => sum,
+    };
+    segment[6..8].copy_from_slice(&sum.to_be_bytes());
+    Ok(segment)
+}
+
+fn ipv4_udp(
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+    src_port: u16,
+    dst_port: u16,
+    message: &[u8],
+) -> Result<Vec<u8>, DnsError> {
+    let udp_len = (8 + message.len()) as u16;
+    let mut pseudo = Vec::with_capacity(12);
+    pseudo.extend_from_slice(&src.octets());
+    pseudo.extend_from_slice(&dst.octets());
+    pseudo.extend_from_slice(&[0, IPPROTO_UDP]);
+    pseudo.extend_from_slice(&udp_len.to_be_bytes());
+    let segment = udp(&pseudo, src_port, dst_port, message)?;
+
+    let total_len = u16::try_from(20 + segment.len())
+        .map_err(|_| invalid(format!("message of {} bytes", message.len())))?;
+    let mut packet = Vec::with_capacity(total_len as usize);
+    packet.extend_from_slice(&[0x45, 0]);
+    packet.extend_from_slice(&total_len.to_be_bytes());
+    // Identification, don't fragment, TTL 64.
+    packet.extend_from_slice(&[0, 0, 0x40, 0, 64, IPPROTO_UDP, 0, 0]);
+    packet.extend_from_slice(&src.octets());
+    packet.extend_from_slice(&dst.octets());
+    let sum = checksum(&[&packet]);
+    packet[10..12].copy_from_slice(&sum.to_be_bytes());
+
+    packet.extend_from_slice(&segment);
+    Ok(packet)
+}
+
+fn ipv6_udp(
+    src: Ipv6Addr,
+    dst: Ipv6Addr,
+    src_port: u16,
+    dst_port: u16,
+    message: &[u8],
+) -> Result<Vec<u8>, DnsError> {
+    let udp_len = (8 + message.len()) as u32;
+    let mut pseudo = Vec::with_capacity(40);
+    pseudo.extend_from_slice(&src.octets());
+    pseudo.extend_from_slice(&dst.octets());
+    pseudo.extend_from_slice(&udp_len.to_be_bytes());
+    pseudo.extend_from_slice(&[0, 0, 0, IPPROTO_UDP]);
+    let segment = udp(&pseudo, src_port, dst_port, message)?;
+
+    let mut packet = Vec::with_capacity(40 + segment.len());
+    packet.extend_from_slice(&[0x60, 0, 0, 0]);
+    packet.extend_from_slice(&(segment.len() as u16).to_be_bytes());
+    packet.extend_from_slice(&[IPPROTO_UDP, 64]);
+    packet.extend_from_slice(&src.octets());
+    packet.extend_from_slice(&dst.octets());
+    packet.extend_from_slice(&segment);
+    Ok(packet)
+}
@@ -0,0 +1,55 @@
+//! DNS 0x20: randomized query name case and the echo check on responses.
+
+use dns_rs::dns::{name::DomainName, question::DnsQuestion, DnsClass, DnsPacket, QueryType};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn name(s: &str) -> DomainName {
+    s.parse().unwrap()
+}
+
+fn query(qname: &str) -> DnsPacket {
+    let mut packet = DnsPacket::default();
+    packet.header.id = 6969;
+    packet
+        .questions
+        .push(DnsQuestion::new(name(qname), QueryType::A));
+    packet
+}
+
+#[test]
+fn randomize_case() {
+    let original = name("www-1.example.com");
+    let randomized = original.randomize_case(&mut StdRng::seed_from_u64(0x20));
+
+    assert_eq!(randomized, original);
+    assert!(!randomized.eq_exact(&original));
+    assert_eq!(randomized.to_string().to_lowercase(), "www-1.example.com.");
+}
+
+#[test]
+fn eq_exact() {
+    assert!(name("Example.COM").eq_exact(&name("Example.COM")));
+    assert!(!name("Example.COM").eq_exact(&name("example.com")));
+    assert_eq!(name("Example.COM"), name("example.com"));
+}
+
+#[test]
+fn response_echoes_query() {
+    let query = query("wWw.ExAmple.cOm");
+    let mut response = query.clone();
+    response.header.response = true;
+    assert!(response.echoes(&query));
+
+    let changes: [fn(&mut DnsPacket); 5] = [
+        |res| res.header.id = 6970,
+        |res| res.questions[0].name = name("www.example.com"),
+        |res| res.questions[0].qtype = QueryType::AAAA,
+        |res| res.questions[0].qclass = DnsClass::CH,
+        |res| res.questions.clear(),
+    ];
+    for change in changes {
+        let mut mismatch = response.clone();
+        change(&mut mismatch);
+        assert!(!mismatch.echoes(&query), "{mismatch:?}");
+    }
+}
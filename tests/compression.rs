@@ -0,0 +1,82 @@
+//! Name compression when writing messages (RFC 1035 section 4.1.4).
+
+use std::net::Ipv4Addr;
+
+use dns_rs::{
+    buffer::{DnsRead, DnsWrite, PacketBuffer},
+    dns::{
+        name::DomainName, question::DnsQuestion, record::DnsRecord, DnsClass, DnsPacket, QueryType,
+    },
+};
+
+fn name(s: &str) -> DomainName {
+    s.parse().unwrap()
+}
+
+#[test]
+fn shared_suffixes_become_pointers() {
+    let mut packet = DnsPacket::default();
+    packet
+        .questions
+        .push(DnsQuestion::new(name("example.com"), QueryType::A));
+    packet.answers.push(DnsRecord::NS {
+        domain: name("example.com"),
+        host: name("ns1.example.com"),
+        class: DnsClass::IN,
+        ttl: 300,
+    });
+    packet.answers.push(DnsRecord::MX {
+        domain: name("example.com"),
+        priority: 10,
+        host: name("mail.example.com"),
+        class: DnsClass::IN,
+        ttl: 300,
+    });
+    packet.answers.push(DnsRecord::A {
+        domain: name("NS1.Example.COM"),
+        addr: Ipv4Addr::new(192, 0, 2, 1),
+        class: DnsClass::IN,
+        ttl: 300,
+    });
+
+    let mut buffer = PacketBuffer::new(0);
+    packet.write(&mut buffer).unwrap();
+
+    // The question name sits at offset 12 and ns1.example.com at 41.
+    let expected: &[u8] = b"\x07example\x03com\x00\x00\x01\x00\x01\
+        \xc0\x0c\x00\x02\x00\x01\x00\x00\x01\x2c\x00\x06\x03ns1\xc0\x0c\
+        \xc0\x0c\x00\x0f\x00\x01\x00\x00\x01\x2c\x00\x09\x00\x0a\x04mail\xc0\x0c\
+        \xc0\x29\x00\x01\x00\x01\x00\x00\x01\x2c\x00\x04\xc0\x00\x02\x01";
+    assert_eq!(&buffer.buf[12..], expected);
+}
+
+#[test]
+fn no_pointers_past_the_offset_limit() {
+    let mut buffer = PacketBuffer::new(0x3ffc);
+    buffer.seek(0x3ffc).unwrap();
+
+    // "example" starts at 0x3ffc and can be pointed to, "com" at 0x4004
+    // and the later names cannot.
+    for qname in ["example.com", "www.example.com", "www.example.com", "com"] {
+        buffer.write_qname(&name(qname)).unwrap();
+    }
+
+    let expected: &[u8] = b"\x07example\x03com\x00\
+        \x03www\xff\xfc\
+        \x03www\xff\xfc\
+        \x03com\x00";
+    assert_eq!(&buffer.buf[0x3ffc..], expected);
+}
+
+#[test]
+fn uncompressed_names_are_written_in_full() {
+    let mut buffer = PacketBuffer::new(0);
+    buffer.write_qname(&name("example.com")).unwrap();
+    buffer
+        .write_qname_uncompressed(&name("example.com"))
+        .unwrap();
+    buffer.write_qname(&name("example.com")).unwrap();
+
+    let expected: &[u8] = b"\x07example\x03com\x00\x07example\x03com\x00\xc0\x00";
+    assert_eq!(buffer.buf, expected);
+}
@@ -0,0 +1,169 @@
+//! DNSSEC helpers: key tags, type bitmaps and the text encodings, checked
+//! against RFC 4034, RFC 5155 and RFC 4648 vectors.
+
+use dns_rs::{
+    buffer::SliceBuffer,
+    dns::{encoding, record::DnsRecord, DnsClass, QueryType},
+};
+
+const DSKEY: &str = "AQOeiiR0GOMYkDshWoSKz9XzfwJr1AYtsmx3TGkJaNXVbfi/2pHm822aJ5iI9BMzNXxeYCmZDRD99WYwYqUSdjMmmAphXdvxegXd/M5+X7OrzKBaMbCVdFLUUh6DhweJBjEVv5f2wwjM9XzcnOf+EPbtG9DMBmADjFDc2w/rljwvFw==";
+
+fn dnskey(algorithm: u8, public_key: Vec<u8>) -> DnsRecord {
+    DnsRecord::DNSKEY {
+        domain: "dskey.example.com".parse().unwrap(),
+        flags: 256,
+        protocol: 3,
+        algorithm,
+        public_key,
+        class: DnsClass::IN,
+        ttl: 86400,
+    }
+}
+
+#[test]
+fn key_tags() {
+    // RFC 4034 section 5.4.
+    let key = encoding::from_base64(DSKEY).unwrap();
+    assert_eq!(dnskey(5, key).key_tag(), Some(60485));
+
+    // RSA/MD5 takes bits of the modulus (RFC 4034 appendix B.1).
+    assert_eq!(
+        dnskey(1, vec![1, 3, 0xab, 0xcd, 0xef]).key_tag(),
+        Some(0xabcd)
+    );
+
+    let ds = DnsRecord::DS {
+        domain: "dskey.example.com".parse().unwrap(),
+        key_tag: 60485,
+        algorithm: 5,
+        digest_type: 1,
+        digest: vec![],
+        class: DnsClass::IN,
+        ttl: 86400,
+    };
+    assert_eq!(ds.key_tag(), None);
+}
+
+fn decode_nsec(bitmap: &[u8]) -> DnsRecord {
+    let mut rdata = b"\x00".to_vec();
+    rdata.extend_from_slice(bitmap);
+    let mut wire = b"\x00\x00\x2f\x00\x01\x00\x00\x00\x00".to_vec();
+    wire.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    wire.extend_from_slice(&rdata);
+    DnsRecord::read(&mut SliceBuffer::new(&wire)).unwrap()
+}
+
+#[test]
+fn type_bitmaps() {
+    let DnsRecord::NSEC { types, .. } = decode_nsec(&[0, 1, 0x40, 1, 1, 0x40]) else {
+        panic!("not NSEC");
+    };
+    assert_eq!(types, [QueryType::A, QueryType::CAA]);
+
+    let malformed: [&[u8]; 6] = [
+        // Windows out of order.
+        &[1, 1, 0x80, 0, 1, 0x40],
+        // An empty window.
+        &[0, 0],
+        // A trailing zero octet.
+        &[0, 2, 0x40, 0],
+        // Longer than 32 octets.
+        &[&[0, 33][..], &[0xff; 33]].concat(),
+        // Running past the RDATA.
+        &[0, 2, 0x40],
+        // A lone window number.
+        &[0],
+    ];
+    for bitmap in malformed {
+        assert!(
+            matches!(decode_nsec(bitmap), DnsRecord::Unknown { qtype: 47, .. }),
+            "{bitmap:?}"
+        );
+    }
+}
+
+#[test]
+fn signature_times() {
+    let record = |expiration: &str| {
+        format!("host.example.com. 86400 IN RRSIG A 5 3 86400 {expiration} 20030220173103 2642 example.com. AQID")
+            .parse::<DnsRecord>()
+    };
+
+    let Ok(DnsRecord::RRSIG {
+        expiration,
+        inception,
+        ..
+    }) = record("20030322173103")
+    else {
+        panic!("not RRSIG");
+    };
+    assert_eq!((expiration, inception), (1048354263, 1045762263));
+
+    // Other lengths are seconds since the epoch.
+    let parsed = record("1048354263").unwrap();
+    assert!(parsed
+        .to_string()
+        .contains(" 20030322173103 20030220173103 "));
+
+    assert!(record("20240229000000").is_ok());
+    for bad in [
+        "20230229000000",
+        "20031322173103",
+        "20030300173103",
+        "20030322243103",
+        "21070101000000",
+        "19691231235959",
+        "2003-03-22",
+        "4294967296",
+    ] {
+        assert!(record(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn base64_vectors() {
+    let vectors = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+    for (data, text) in vectors {
+        assert_eq!(encoding::to_base64(data.as_bytes()), text);
+        assert_eq!(encoding::from_base64(text).unwrap(), data.as_bytes());
+    }
+    assert_eq!(encoding::from_base64("Zm9v YmFy").unwrap(), b"foobar");
+
+    for bad in ["Zg", "Zg=", "Z===", "Zg==Zg==", "Zm9v!mFy"] {
+        assert_eq!(encoding::from_base64(bad), None, "{bad}");
+    }
+}
+
+#[test]
+fn base32hex_vectors() {
+    let vectors = [
+        ("", ""),
+        ("f", "co"),
+        ("fo", "cpng"),
+        ("foo", "cpnmu"),
+        ("foob", "cpnmuog"),
+        ("fooba", "cpnmuoj1"),
+        ("foobar", "cpnmuoj1e8"),
+    ];
+    for (data, text) in vectors {
+        assert_eq!(encoding::to_base32hex(data.as_bytes()), text);
+        assert_eq!(encoding::from_base32hex(text).unwrap(), data.as_bytes());
+        assert_eq!(
+            encoding::from_base32hex(&text.to_uppercase()).unwrap(),
+            data.as_bytes()
+        );
+    }
+
+    // A lone digit, leftover bits that are not zero, and padding.
+    for bad in ["c", "cp", "cpnmuoj1e9", "co======", "w0"] {
+        assert_eq!(encoding::from_base32hex(bad), None, "{bad}");
+    }
+}
@@ -0,0 +1,257 @@
+//! EDNS(0): the OPT record, its options and payload size negotiation
+//! (RFC 6891).
+
+use std::net::IpAddr;
+
+use dns_rs::{
+    buffer::{PacketBuffer, SliceBuffer, UDP_PACKET_SIZE},
+    dns::{
+        edns::{Edns, EdnsOption, EDNS_PAYLOAD_SIZE},
+        packet_ref::DnsPacketRef,
+        DnsPacket, ResCode,
+    },
+    error::{DnsError, Malformation, Section},
+};
+
+const HEADER: &[u8] = b"\x12\x34\x01\x00\x00\x01\x00\x00\x00\x00\x00\x01";
+const QUESTION: &[u8] = b"\x07example\x03com\x00\x00\x01\x00\x01";
+
+/// A query for example.com A carrying an OPT record with `ttl` and the
+/// encoded `options`.
+fn query(ttl: u32, options: &[u8]) -> Vec<u8> {
+    let mut wire = [HEADER, QUESTION, b"\x00\x00\x29\x04\xd0"].concat();
+    wire.extend_from_slice(&ttl.to_be_bytes());
+    wire.extend_from_slice(&(options.len() as u16).to_be_bytes());
+    wire.extend_from_slice(options);
+    wire
+}
+
+fn decode(wire: &[u8]) -> Result<DnsPacket, DnsError> {
+    DnsPacket::from_buffer(&mut SliceBuffer::new(wire))
+}
+
+fn option(code: u16, data: &[u8]) -> EdnsOption {
+    let mut options = code.to_be_bytes().to_vec();
+    options.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    options.extend_from_slice(data);
+    let packet = decode(&query(0, &options)).unwrap();
+    packet.edns.unwrap().options.remove(0)
+}
+
+#[test]
+fn opt_record() {
+    let wire = query(
+        0x0000_8000,
+        b"\x00\x0a\x00\x08\x01\x23\x45\x67\x89\xab\xcd\xef\
+          \x00\x08\x00\x07\x00\x01\x18\x00\xc0\x00\x02",
+    );
+    let mut packet = decode(&wire).unwrap();
+    assert!(packet.resources.is_empty());
+    assert_eq!(
+        packet.edns,
+        Some(Edns {
+            udp_payload_size: 1232,
+            extended_rcode: 0,
+            version: 0,
+            dnssec_ok: true,
+            options: vec![
+                EdnsOption::Cookie {
+                    client: *b"\x01\x23\x45\x67\x89\xab\xcd\xef",
+                    server: vec![],
+                },
+                EdnsOption::ClientSubnet {
+                    source_prefix: 24,
+                    scope_prefix: 0,
+                    address: "192.0.2.0".parse().unwrap(),
+                },
+            ],
+        })
+    );
+
+    let mut buffer = PacketBuffer::new(0);
+    packet.write(&mut buffer).unwrap();
+    assert_eq!(buffer.buf, wire);
+
+    let view = DnsPacketRef::new(&wire).unwrap();
+    assert_eq!(view.edns().unwrap(), packet.edns);
+}
+
+#[test]
+fn extended_rcode() {
+    let mut packet = decode(&query(0x0100_0000, b"")).unwrap();
+    assert_eq!(packet.header.rescode, ResCode::BADVERS);
+
+    // Writing takes the upper bits from the header again.
+    packet.header.rescode = ResCode::BADCOOKIE;
+    let mut buffer = PacketBuffer::new(0);
+    packet.write(&mut buffer).unwrap();
+    assert_eq!(packet.edns.unwrap().extended_rcode, 1);
+    assert_eq!(buffer.buf[3] & 0x0f, 7);
+}
+
+#[test]
+fn extended_rcode_without_edns() {
+    assert_eq!(ResCode::NXDOMAIN.without_edns(), ResCode::NXDOMAIN);
+    assert_eq!(ResCode::NOTZONE.without_edns(), ResCode::NOTZONE);
+    assert_eq!(ResCode::BADVERS.without_edns(), ResCode::SERVFAIL);
+    assert_eq!(ResCode::BADCOOKIE.without_edns(), ResCode::SERVFAIL);
+    assert_eq!(ResCode::Unknown(4095).without_edns(), ResCode::SERVFAIL);
+}
+
+#[test]
+fn typed_options() {
+    assert_eq!(option(3, b"ns1"), EdnsOption::Nsid(b"ns1".to_vec()));
+    assert_eq!(
+        option(8, b"\x00\x02\x38\x00\x20\x01\x0d\xb8\x12\x34\x56"),
+        EdnsOption::ClientSubnet {
+            source_prefix: 56,
+            scope_prefix: 0,
+            address: "2001:db8:1234:5600::".parse().unwrap(),
+        }
+    );
+    assert_eq!(
+        option(8, b"\x00\x01\x00\x00"),
+        EdnsOption::ClientSubnet {
+            source_prefix: 0,
+            scope_prefix: 0,
+            address: IpAddr::from([0, 0, 0, 0]),
+        }
+    );
+    assert_eq!(option(11, b""), EdnsOption::Keepalive(None));
+    assert_eq!(option(11, b"\x01\x2c"), EdnsOption::Keepalive(Some(300)));
+    assert_eq!(option(12, &[0; 5]), EdnsOption::Padding(5));
+    assert_eq!(
+        option(15, b"\x00\x12blocked"),
+        EdnsOption::Ede {
+            code: 18,
+            text: "blocked".to_string(),
+        }
+    );
+}
+
+#[test]
+fn malformed_options_stay_raw() {
+    let cases: [(u16, &[u8]); 9] = [
+        // Family 3, a prefix longer than the address, bits past the
+        // prefix, and an address longer than the prefix.
+        (8, b"\x00\x03\x00\x00"),
+        (8, b"\x00\x01\x21\x00\xc0\x00\x02\x00\x00"),
+        (8, b"\x00\x01\x17\x00\xc0\x00\x03"),
+        (8, b"\x00\x01\x10\x00\xc0\x00\x02"),
+        // Cookies of 5 and 12 bytes.
+        (10, b"\x01\x02\x03\x04\x05"),
+        (10, b"\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c"),
+        (11, b"\x01"),
+        (12, b"\x00\x01"),
+        (15, b"\x00\x12\xff"),
+    ];
+    for (code, data) in cases {
+        assert_eq!(
+            option(code, data),
+            EdnsOption::Unknown {
+                code,
+                data: data.to_vec(),
+            },
+            "{code} {data:?}"
+        );
+    }
+}
+
+#[test]
+fn rejects_bad_opt_records() {
+    let truncated = query(0, b"\x00\x0a\x00\x08\x01\x23");
+    let mut duplicate = query(0, b"");
+    duplicate[11] = 2;
+    duplicate.extend_from_slice(b"\x00\x00\x29\x02\x00\x00\x00\x00\x00\x00\x00");
+
+    // The OPT records start after the 29 bytes of header and question.
+    for (wire, offset, cause) in [
+        (truncated, 29, Malformation::TruncatedOption),
+        (duplicate, 40, Malformation::DuplicateOpt),
+    ] {
+        let expected = DnsError::Malformed {
+            offset,
+            section: Some(Section::Additional),
+            cause,
+        };
+        assert_eq!(decode(&wire), Err(expected.clone()));
+        assert_eq!(DnsPacketRef::new(&wire).unwrap().edns(), Err(expected));
+    }
+}
+
+#[test]
+fn rejects_invalid_options_on_write() {
+    let invalid = [
+        EdnsOption::Cookie {
+            client: [0; 8],
+            server: vec![0; 4],
+        },
+        EdnsOption::ClientSubnet {
+            source_prefix: 33,
+            scope_prefix: 0,
+            address: "192.0.2.0".parse().unwrap(),
+        },
+        EdnsOption::ClientSubnet {
+            source_prefix: 16,
+            scope_prefix: 0,
+            address: "192.0.2.0".parse().unwrap(),
+        },
+    ];
+    for option in invalid {
+        let mut packet = DnsPacket {
+            edns: Some(Edns {
+                options: vec![option.clone()],
+                ..Edns::default()
+            }),
+            ..DnsPacket::default()
+        };
+        assert!(
+            matches!(
+                packet.write(&mut PacketBuffer::new(0)),
+                Err(DnsError::InvalidRecord(_))
+            ),
+            "{option:?}"
+        );
+    }
+}
+
+#[test]
+fn payload_size_negotiation() {
+    let size = |udp_payload_size| {
+        Edns {
+            udp_payload_size,
+            ..Edns::default()
+        }
+        .response_size()
+    };
+    assert_eq!(size(0), UDP_PACKET_SIZE);
+    assert_eq!(size(1000), 1000);
+    assert_eq!(size(4096), EDNS_PAYLOAD_SIZE as usize);
+}
+
+#[test]
+fn truncates_to_the_limit() {
+    let mut packet = decode(&query(0, b"")).unwrap();
+    for i in 0..40 {
+        packet.answers.push(
+            format!("host{i}.example.com. 300 IN A 192.0.2.{i}")
+                .parse()
+                .unwrap(),
+        );
+    }
+
+    let mut full = packet.clone();
+    let buffer = full.write_udp(EDNS_PAYLOAD_SIZE as usize).unwrap();
+    assert!(!full.header.truncated_message);
+    assert_eq!(full.answers.len(), 40);
+    assert_eq!(decode(&buffer.buf).unwrap(), full);
+
+    let buffer = packet.write_udp(UDP_PACKET_SIZE).unwrap();
+    assert!(buffer.buf.len() <= UDP_PACKET_SIZE);
+    assert!(packet.header.truncated_message);
+    assert!(packet.answers.is_empty());
+
+    let decoded = decode(&buffer.buf).unwrap();
+    assert_eq!(decoded.questions.len(), 1);
+    assert!(decoded.edns.is_some());
+}
@@ -0,0 +1,132 @@
+//! RFC 8427 JSON vectors, checked against `serde_json::Value` so member
+//! order does not matter.
+#![cfg(feature = "json")]
+
+use std::net::Ipv4Addr;
+
+use dns_rs::{
+    dns::{question::DnsQuestion, record::DnsRecord, DnsClass, DnsPacket, QueryType, ResCode},
+    error::DnsError,
+};
+use serde_json::{json, Value};
+
+fn response() -> DnsPacket {
+    let mut packet = DnsPacket::default();
+    packet.header.id = 0x1234;
+    packet.header.response = true;
+    packet.header.recursion_desired = true;
+    packet.header.recursion_available = true;
+    packet.header.rescode = ResCode::NOERROR;
+    packet.header.questions = 1;
+    packet.header.answers = 2;
+    packet.questions.push(DnsQuestion::new(
+        "example.com".parse().unwrap(),
+        QueryType::A,
+    ));
+    packet.answers.push(DnsRecord::A {
+        domain: "example.com".parse().unwrap(),
+        addr: Ipv4Addr::new(192, 0, 2, 1),
+        class: DnsClass::IN,
+        ttl: 300,
+    });
+    packet.answers.push(DnsRecord::Unknown {
+        domain: "example.com".parse().unwrap(),
+        qtype: 65280,
+        data: vec![0xde, 0xad],
+        class: DnsClass::IN,
+        ttl: 60,
+    });
+    packet
+}
+
+#[test]
+fn packet_to_json() {
+    let value: Value = serde_json::from_str(&response().to_json().unwrap()).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "ID": 0x1234, "QR": 1, "Opcode": 0, "AA": 0, "TC": 0, "RD": 1, "RA": 1,
+            "AD": 0, "CD": 0, "RCODE": 0,
+            "QDCOUNT": 1, "ANCOUNT": 2, "NSCOUNT": 0, "ARCOUNT": 0,
+            "questionRRs": [{ "NAME": "example.com.", "TYPE": 1, "CLASS": 1 }],
+            "answerRRs": [
+                {
+                    "NAME": "example.com.", "TYPE": 1, "CLASS": 1, "TTL": 300,
+                    "RDLENGTH": 4, "RDATAHEX": "c0000201", "rdataA": "192.0.2.1"
+                },
+                {
+                    "NAME": "example.com.", "TYPE": 65280, "CLASS": 1, "TTL": 60,
+                    "RDLENGTH": 2, "RDATAHEX": "dead"
+                }
+            ]
+        })
+    );
+}
+
+#[test]
+fn packet_from_json_roundtrip() {
+    let packet = response();
+    assert_eq!(
+        DnsPacket::from_json(&packet.to_json().unwrap()).unwrap(),
+        packet
+    );
+}
+
+#[test]
+fn hand_written_fixture() {
+    // Booleans, missing counts and the single-question shorthand are all
+    // accepted on input.
+    let packet = DnsPacket::from_json(
+        r#"{
+            "ID": 4660, "QR": true, "RD": true, "RA": true,
+            "QNAME": "example.com", "QTYPE": 1,
+            "answerRRs": [
+                { "NAME": "example.com", "TYPE": 1, "TTL": 300, "rdataA": "192.0.2.1" },
+                { "NAME": "example.com", "TYPE": 65280, "TTL": 60, "RDATAHEX": "DE AD" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(packet, response());
+}
+
+#[test]
+fn name_rdata_members() {
+    let record: DnsRecord = serde_json::from_value(json!({
+        "NAME": "www.example.com.", "TYPE": 5, "CLASS": 1, "TTL": 60,
+        "rdataCNAME": "example.com."
+    }))
+    .unwrap();
+    assert_eq!(
+        record,
+        DnsRecord::CNAME {
+            domain: "www.example.com".parse().unwrap(),
+            host: "example.com".parse().unwrap(),
+            class: DnsClass::IN,
+            ttl: 60,
+        }
+    );
+
+    let value = serde_json::to_value(&record).unwrap();
+    assert_eq!(value["RDATAHEX"], "076578616d706c6503636f6d00");
+    assert_eq!(value["rdataCNAME"], "example.com.");
+}
+
+#[test]
+fn rejects_bad_input() {
+    let cases = [
+        "not json",
+        r#"{ "answerRRs": [{ "NAME": "example.com", "TYPE": 1 }] }"#,
+        r#"{ "answerRRs": [{ "NAME": "example.com", "TYPE": 1, "RDATAHEX": "zz" }] }"#,
+        r#"{ "answerRRs": [{ "NAME": "example.com", "TYPE": 1, "RDATAHEX": "c00002" }] }"#,
+        r#"{ "questionRRs": [{ "NAME": "a..b", "TYPE": 1 }] }"#,
+    ];
+
+    for case in cases {
+        assert!(
+            matches!(DnsPacket::from_json(case), Err(DnsError::InvalidJson(_))),
+            "{case}"
+        );
+    }
+}
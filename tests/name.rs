@@ -0,0 +1,63 @@
+//! Domain name presentation format escapes (RFC 1035 section 5.1).
+
+use dns_rs::dns::name::DomainName;
+
+/// Parses `text`, checks its labels and that it prints back as `shown`.
+fn assert_name(text: &str, labels: &[&[u8]], shown: &str) {
+    let name: DomainName = text.parse().unwrap();
+    assert_eq!(name.labels().collect::<Vec<_>>(), labels, "{text}");
+    assert_eq!(name.to_string(), shown);
+
+    let reparsed: DomainName = shown.parse().unwrap();
+    assert!(reparsed.eq_exact(&name), "{shown}");
+}
+
+#[test]
+fn escaped_dot() {
+    assert_name(
+        r"a\.b.example.com",
+        &[b"a.b", b"example", b"com"],
+        r"a\.b.example.com.",
+    );
+    assert_name(
+        r"a\046b.example.com.",
+        &[b"a.b", b"example", b"com"],
+        r"a\.b.example.com.",
+    );
+}
+
+#[test]
+fn escaped_backslash() {
+    assert_name(r"a\\b.example.", &[br"a\b", b"example"], r"a\\b.example.");
+}
+
+#[test]
+fn non_ascii_bytes() {
+    assert_name(
+        r"caf\233.example.",
+        &[b"caf\xe9", b"example"],
+        r"caf\233.example.",
+    );
+    assert_name(
+        r"\000\ .example.",
+        &[b"\x00\x20", b"example"],
+        r"\000\032.example.",
+    );
+
+    let name = DomainName::from_labels([&b"caf\xe9"[..], b"example"]).unwrap();
+    assert_eq!(name.to_string(), r"caf\233.example.");
+}
+
+#[test]
+fn rejects_bad_escapes_and_empty_labels() {
+    let cases = [
+        r"\256.example.",
+        r"\25.example.",
+        r"a\",
+        "a..example.",
+        ".example.",
+    ];
+    for case in cases {
+        assert!(case.parse::<DomainName>().is_err(), "{case}");
+    }
+}
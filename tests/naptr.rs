@@ -0,0 +1,73 @@
+//! RFC 3402 substitution expressions, with the ENUM rules of RFC 6116.
+#![cfg(feature = "regexp")]
+
+use dns_rs::{
+    dns::{naptr::apply_regexp, record::DnsRecord},
+    error::DnsError,
+};
+
+fn apply(regexp: &str, input: &str) -> Option<String> {
+    apply_regexp(regexp.as_bytes(), input).unwrap()
+}
+
+#[test]
+fn enum_rewrites() {
+    assert_eq!(
+        apply(r"!^\+44(.*)$!sip:\1@example.co.uk!", "+442079460123").as_deref(),
+        Some("sip:2079460123@example.co.uk")
+    );
+    assert_eq!(
+        apply(r"!^.*$!mailto:info@example.com!", "+4420").as_deref(),
+        Some("mailto:info@example.com")
+    );
+    assert_eq!(apply(r"!^\+1(.*)$!sip:\1@example.com!", "+4420"), None);
+}
+
+#[test]
+fn record_from_text() {
+    let record: DnsRecord = r#"4.3.2.1.5.5.5.0.0.8.1.e164.arpa. 300 IN NAPTR 100 10 "u" "E2U+sip" "!^.*$!sip:info@example.com!" ."#
+        .parse()
+        .unwrap();
+    let DnsRecord::NAPTR { regexp, .. } = record else {
+        panic!("{record:?}");
+    };
+    assert_eq!(
+        apply_regexp(&regexp, "+18005551234").unwrap().as_deref(),
+        Some("sip:info@example.com")
+    );
+}
+
+#[test]
+fn delimiters_flags_and_escapes() {
+    // Any delimiter works, and an escaped one is literal.
+    assert_eq!(apply(r"#^(.*)$#x\#\1#", "abc").as_deref(), Some("x#abc"));
+    assert_eq!(apply(r"/a\/b/c/", "xa/by").as_deref(), Some("xcy"));
+    assert_eq!(apply(r"!\\!/!", r"a\b").as_deref(), Some("a/b"));
+
+    assert_eq!(apply("!abc!X!", "ABC"), None);
+    assert_eq!(apply("!abc!X!i", "ABC").as_deref(), Some("X"));
+
+    // An optional group that did not take part expands to nothing.
+    assert_eq!(apply(r"!^(x)?(.*)$!\1-\2!", "abc").as_deref(), Some("-abc"));
+}
+
+#[test]
+fn rejects_bad_expressions() {
+    let cases: [&[u8]; 9] = [
+        b"",
+        b"!abc!X",
+        b"!abc!X!!",
+        b"!abc!X!g",
+        b"1abc1X1",
+        b"iabciXi",
+        b"!(!X!",
+        br"!(a)!\2!",
+        b"!a!\xff!",
+    ];
+    for case in cases {
+        assert!(
+            matches!(apply_regexp(case, "a"), Err(DnsError::InvalidRecord(_))),
+            "{case:?}"
+        );
+    }
+}
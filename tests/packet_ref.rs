@@ -0,0 +1,175 @@
+//! The borrowed `DnsPacketRef` view: lazy sections, compressed names and
+//! how decode errors end iteration.
+
+use std::net::Ipv4Addr;
+
+use dns_rs::{
+    dns::{name::DomainName, packet_ref::DnsPacketRef, record::DnsRecord, DnsClass},
+    error::{DnsError, Malformation, Section},
+};
+
+const HEADER: &[u8] = b"\x12\x34\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00";
+const QUESTION: &[u8] = b"\x07example\x03com\x00\x00\x01\x00\x01";
+
+/// A response to `example.com. MX` with two answers and one authority,
+/// every name but the question's compressed.
+const GOLDEN: &[u8] = b"\x12\x34\x81\x80\x00\x01\x00\x02\x00\x01\x00\x00\
+    \x07example\x03com\x00\x00\x0f\x00\x01\
+    \xc0\x0c\x00\x0f\x00\x01\x00\x00\x0e\x10\x00\x09\x00\x0a\x04mail\xc0\x0c\
+    \xc0\x2b\x00\x01\x00\x01\x00\x00\x0e\x10\x00\x04\xc0\x00\x02\x19\
+    \xc0\x0c\x00\x02\x00\x01\x00\x01\x51\x80\x00\x06\x03ns1\xc0\x0c";
+
+/// A response to `example.com. A` with one CNAME answer whose owner and
+/// target both point back into the question.
+fn response(rdlength: u8, rdata: &[u8]) -> Vec<u8> {
+    let answer = [
+        b"\x03www\xc0\x0c\x00\x05\x00\x01\x00\x00\x01\x2c\x00",
+        &[rdlength][..],
+        rdata,
+    ]
+    .concat();
+    [HEADER, QUESTION, &answer].concat()
+}
+
+fn name(s: &str) -> DomainName {
+    s.parse().unwrap()
+}
+
+#[test]
+fn golden_response() {
+    let view = DnsPacketRef::new(GOLDEN).unwrap();
+    let packet = view.to_packet().unwrap();
+
+    let questions: Vec<_> = view.questions().map(Result::unwrap).collect();
+    assert_eq!(questions.len(), 1);
+    assert_eq!(questions[0].name.to_string(), "example.com.");
+    assert_eq!(u16::from(questions[0].qtype), 15);
+
+    let answers = [
+        DnsRecord::MX {
+            domain: name("example.com"),
+            priority: 10,
+            host: name("mail.example.com"),
+            class: DnsClass::IN,
+            ttl: 3600,
+        },
+        DnsRecord::A {
+            domain: name("mail.example.com"),
+            addr: Ipv4Addr::new(192, 0, 2, 25),
+            class: DnsClass::IN,
+            ttl: 3600,
+        },
+    ];
+    let authorities = [DnsRecord::NS {
+        domain: name("example.com"),
+        host: name("ns1.example.com"),
+        class: DnsClass::IN,
+        ttl: 86400,
+    }];
+
+    let sections = [
+        (view.answers().unwrap(), &answers[..], &packet.answers),
+        (
+            view.authorities().unwrap(),
+            &authorities[..],
+            &packet.authorities,
+        ),
+    ];
+    for (records, expected, decoded) in sections {
+        let records: Vec<_> = records.map(Result::unwrap).collect();
+        assert_eq!(records.len(), expected.len());
+        assert_eq!(decoded, expected);
+        for (record, expected) in records.iter().zip(expected) {
+            assert_eq!(&record.to_record().unwrap(), expected);
+        }
+    }
+    assert!(view.resources().unwrap().next().is_none());
+}
+
+#[test]
+fn compressed_names() {
+    let wire = response(2, b"\xc0\x0c");
+    let view = DnsPacketRef::new(&wire).unwrap();
+    assert_eq!(view.header.id, 0x1234);
+
+    let question = view.questions().next().unwrap().unwrap();
+    assert_eq!(question.name.to_string(), "example.com.");
+    assert_eq!(u16::from(question.qtype), 1);
+
+    let mut answers = view.answers().unwrap();
+    let answer = answers.next().unwrap().unwrap();
+    assert!(answers.next().is_none());
+
+    assert_eq!(
+        answer.domain.labels().collect::<Vec<_>>(),
+        [&b"www"[..], b"example", b"com"]
+    );
+    assert_eq!(answer.domain.to_string(), "www.example.com.");
+    assert_eq!(answer.domain.to_name(), name("www.example.com"));
+    assert_eq!(
+        (u16::from(answer.qtype), answer.class, answer.ttl),
+        (5, DnsClass::IN, 300)
+    );
+    assert_eq!(answer.data, b"\xc0\x0c");
+    assert_eq!(
+        answer.to_record().unwrap(),
+        DnsRecord::CNAME {
+            domain: name("www.example.com"),
+            host: name("example.com"),
+            class: DnsClass::IN,
+            ttl: 300,
+        }
+    );
+
+    assert!(view.authorities().unwrap().next().is_none());
+    assert!(view.resources().unwrap().next().is_none());
+    assert_eq!(view.as_bytes(), wire);
+}
+
+#[test]
+fn truncated_rdata_ends_iteration() {
+    let wire = response(4, b"\xc0\x0c");
+    let view = DnsPacketRef::new(&wire).unwrap();
+
+    let mut answers = view.answers().unwrap();
+    assert_eq!(
+        answers.next().unwrap().unwrap_err(),
+        DnsError::Malformed {
+            offset: 45,
+            section: Some(Section::Answer),
+            cause: Malformation::TruncatedRdata { len: 4 },
+        }
+    );
+    assert!(answers.next().is_none());
+    assert!(view.authorities().is_err());
+}
+
+#[test]
+fn malformed_name_ends_iteration() {
+    // Two questions, the first a pointer to itself.
+    let mut wire = HEADER.to_vec();
+    wire[5] = 2;
+    wire.extend_from_slice(b"\xc0\x0c\x00\x01\x00\x01");
+    let view = DnsPacketRef::new(&wire).unwrap();
+
+    let mut questions = view.questions();
+    assert!(matches!(
+        questions.next(),
+        Some(Err(DnsError::Malformed {
+            offset: 12,
+            section: Some(Section::Question),
+            cause: Malformation::ForwardPointer { .. },
+        }))
+    ));
+    assert!(questions.next().is_none());
+    assert!(view.answers().is_err());
+    assert!(view.to_packet().is_err());
+}
+
+#[test]
+fn only_the_header_is_decoded_up_front() {
+    let mut wire = HEADER.to_vec();
+    wire.push(0xff);
+    assert!(DnsPacketRef::new(&wire).is_ok());
+    assert!(DnsPacketRef::new(&wire[..11]).is_err());
+}
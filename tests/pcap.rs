@@ -0,0 +1,271 @@
+//! Captures written by `PcapWriter` must read back, and hand-built pcapng
+//! files exercise the link layers and block types we never write.
+
+use std::{
+    net::SocketAddr,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use dns_rs::{
+    dns::{question::DnsQuestion, DnsPacket, QueryType},
+    error::DnsError,
+    pcap::{self, PcapWriter, Transport},
+};
+
+fn query() -> DnsPacket {
+    let mut packet = DnsPacket::default();
+    packet.header.id = 0x1234;
+    packet.header.recursion_desired = true;
+    packet.questions.push(DnsQuestion::new(
+        "example.com".parse().unwrap(),
+        QueryType::A,
+    ));
+    packet
+}
+
+fn addr(s: &str) -> SocketAddr {
+    s.parse().unwrap()
+}
+
+/// A pcap holding one query from `src` to `dst`.
+fn written(src: &str, dst: &str) -> Vec<u8> {
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    writer
+        .write_packet(
+            UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456),
+            addr(src),
+            addr(dst),
+            &mut query(),
+        )
+        .unwrap();
+    writer.into_inner()
+}
+
+/// The raw IP packet of the single record in a pcap from `written`.
+fn ip_packet(pcap: &[u8]) -> &[u8] {
+    &pcap[24 + 16..]
+}
+
+/// Sums 16 bit words like the IP checksum; a valid header sums to zero.
+fn fold(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]) as u32)
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[test]
+fn writes_ipv4_udp() {
+    let pcap = written("192.0.2.1:40000", "192.0.2.53:53");
+    let ip = ip_packet(&pcap);
+
+    assert_eq!(&pcap[..4], &[0xd4, 0xc3, 0xb2, 0xa1]);
+    assert_eq!(&pcap[20..24], &101u32.to_le_bytes());
+    assert_eq!(ip[0], 0x45);
+    assert_eq!(fold(&ip[..20]), 0);
+
+    let mut pseudo = ip[12..20].to_vec();
+    pseudo.extend_from_slice(&[0, 17]);
+    pseudo.extend_from_slice(&ip[24..26]);
+    pseudo.extend_from_slice(&ip[20..]);
+    assert_eq!(fold(&pseudo), 0);
+
+    let captures = pcap::read(&pcap).unwrap();
+    assert_eq!(captures.len(), 1);
+    assert_eq!(captures[0].src, addr("192.0.2.1:40000"));
+    assert_eq!(captures[0].dst, addr("192.0.2.53:53"));
+    assert_eq!(captures[0].transport, Transport::Udp);
+    assert_eq!(
+        captures[0].timestamp,
+        Duration::from_micros(1_700_000_000_123_456)
+    );
+
+    let mut expected = query();
+    expected.header.questions = 1;
+    assert_eq!(captures[0].decode().unwrap(), expected);
+}
+
+#[test]
+fn writes_ipv6_udp() {
+    let pcap = written("[2001:db8::1]:53", "[2001:db8::2]:40000");
+    let ip = ip_packet(&pcap);
+    assert_eq!(ip[0] >> 4, 6);
+
+    let captures = pcap::read(&pcap).unwrap();
+    assert_eq!(captures.len(), 1);
+    assert_eq!(captures[0].src, addr("[2001:db8::1]:53"));
+    assert_eq!(captures[0].decode().unwrap().questions, query().questions);
+}
+
+#[test]
+fn filters_ports() {
+    let pcap = written("192.0.2.1:40000", "192.0.2.53:2069");
+    assert!(pcap::read(&pcap).unwrap().is_empty());
+    assert_eq!(pcap::read_with_ports(&pcap, &[2069]).unwrap().len(), 1);
+}
+
+#[test]
+fn reads_big_endian_nanosecond_pcap() {
+    let pcap = written("192.0.2.1:40000", "192.0.2.53:53");
+    let ip = ip_packet(&pcap);
+
+    let mut file = Vec::new();
+    file.extend_from_slice(&0xa1b2_3c4du32.to_be_bytes());
+    file.extend_from_slice(&[0, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff]);
+    file.extend_from_slice(&101u32.to_be_bytes());
+    file.extend_from_slice(&10u32.to_be_bytes());
+    file.extend_from_slice(&5u32.to_be_bytes());
+    file.extend_from_slice(&(ip.len() as u32).to_be_bytes());
+    file.extend_from_slice(&(ip.len() as u32).to_be_bytes());
+    file.extend_from_slice(ip);
+
+    let captures = pcap::read(&file).unwrap();
+    assert_eq!(captures.len(), 1);
+    assert_eq!(captures[0].timestamp, Duration::new(10, 5));
+}
+
+/// Appends a little-endian pcapng block padded to 32 bits.
+fn block(file: &mut Vec<u8>, kind: u32, body: &[u8]) {
+    let padded = body.len().div_ceil(4) * 4;
+    let len = (12 + padded) as u32;
+    file.extend_from_slice(&kind.to_le_bytes());
+    file.extend_from_slice(&len.to_le_bytes());
+    file.extend_from_slice(body);
+    file.resize(file.len() + padded - body.len(), 0);
+    file.extend_from_slice(&len.to_le_bytes());
+}
+
+fn section_header(file: &mut Vec<u8>) {
+    let mut body = 0x1a2b_3c4du32.to_le_bytes().to_vec();
+    body.extend_from_slice(&[1, 0, 0, 0]);
+    body.extend_from_slice(&u64::MAX.to_le_bytes());
+    block(file, 0x0a0d_0d0a, &body);
+}
+
+fn interface(file: &mut Vec<u8>, linktype: u16, tsresol: Option<u8>) {
+    let mut body = linktype.to_le_bytes().to_vec();
+    body.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    if let Some(resol) = tsresol {
+        body.extend_from_slice(&[9, 0, 1, 0, resol, 0, 0, 0]);
+        body.extend_from_slice(&[0, 0, 0, 0]);
+    }
+    block(file, 1, &body);
+}
+
+fn enhanced_packet(file: &mut Vec<u8>, interface: u32, ticks: u64, data: &[u8]) {
+    let mut body = interface.to_le_bytes().to_vec();
+    body.extend_from_slice(&((ticks >> 32) as u32).to_le_bytes());
+    body.extend_from_slice(&(ticks as u32).to_le_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    body.extend_from_slice(data);
+    block(file, 6, &body);
+}
+
+#[test]
+fn reads_pcapng_link_layers() {
+    let pcap = written("192.0.2.1:40000", "192.0.2.53:53");
+    let ip = ip_packet(&pcap);
+
+    let mut ethernet = vec![0; 12];
+    ethernet.extend_from_slice(&[0x81, 0x00, 0x00, 0x2a, 0x08, 0x00]);
+    ethernet.extend_from_slice(ip);
+
+    let mut sll = vec![0; 14];
+    sll.extend_from_slice(&[0x08, 0x00]);
+    sll.extend_from_slice(ip);
+
+    let mut null = 2u32.to_le_bytes().to_vec();
+    null.extend_from_slice(ip);
+
+    let mut file = Vec::new();
+    section_header(&mut file);
+    interface(&mut file, 1, None);
+    interface(&mut file, 113, Some(9));
+    interface(&mut file, 0, Some(0x80 | 10));
+    enhanced_packet(&mut file, 0, 1_500_000, &ethernet);
+    enhanced_packet(&mut file, 1, 2_000_000_001, &sll);
+    enhanced_packet(&mut file, 2, 3 << 10, &null);
+    block(&mut file, 3, &{
+        let mut body = (ethernet.len() as u32).to_le_bytes().to_vec();
+        body.extend_from_slice(&ethernet);
+        body
+    });
+    // Unknown blocks are skipped.
+    block(&mut file, 0x0bad, &[1, 2, 3]);
+
+    let captures = pcap::read(&file).unwrap();
+    let timestamps: Vec<_> = captures.iter().map(|c| c.timestamp).collect();
+    assert_eq!(
+        timestamps,
+        [
+            Duration::from_micros(1_500_000),
+            Duration::new(2, 1),
+            Duration::from_secs(3),
+            Duration::ZERO,
+        ]
+    );
+    for capture in captures {
+        assert_eq!(capture.src, addr("192.0.2.1:40000"));
+        assert_eq!(capture.decode().unwrap().questions, query().questions);
+    }
+}
+
+#[test]
+fn reads_tcp_messages() {
+    let mut message = Vec::new();
+    query().write(&mut message).unwrap();
+
+    let mut tcp = Vec::new();
+    tcp.extend_from_slice(&53u16.to_be_bytes());
+    tcp.extend_from_slice(&40000u16.to_be_bytes());
+    tcp.extend_from_slice(&[0; 8]);
+    tcp.extend_from_slice(&[0x50, 0x18, 0xff, 0xff, 0, 0, 0, 0]);
+    for _ in 0..2 {
+        tcp.extend_from_slice(&(message.len() as u16).to_be_bytes());
+        tcp.extend_from_slice(&message);
+    }
+    // The start of a third message continues in the next segment.
+    tcp.extend_from_slice(&(message.len() as u16).to_be_bytes());
+    tcp.extend_from_slice(&message[..4]);
+
+    let mut ip = vec![0x45, 0];
+    ip.extend_from_slice(&((20 + tcp.len()) as u16).to_be_bytes());
+    ip.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0, 192, 0, 2, 53, 192, 0, 2, 1]);
+    ip.extend_from_slice(&tcp);
+
+    let mut file = Vec::new();
+    section_header(&mut file);
+    interface(&mut file, 101, None);
+    enhanced_packet(&mut file, 0, 0, &ip);
+
+    let captures = pcap::read(&file).unwrap();
+    assert_eq!(captures.len(), 2);
+    for capture in captures {
+        assert_eq!(capture.transport, Transport::Tcp);
+        assert_eq!(capture.src, addr("192.0.2.53:53"));
+        assert_eq!(capture.payload, message);
+    }
+}
+
+#[test]
+fn rejects_bad_files() {
+    let pcap = written("192.0.2.1:40000", "192.0.2.53:53");
+
+    let cases: [&[u8]; 4] = [
+        b"",
+        b"\x00\x01\x02\x03 not a capture file",
+        &pcap[..pcap.len() - 1],
+        &[0x0a, 0x0d, 0x0d, 0x0a, 0x0c, 0, 0, 0, 0, 0, 0, 0],
+    ];
+    for case in cases {
+        assert!(
+            matches!(pcap::read(case), Err(DnsError::InvalidCapture(_))),
+            "{case:?}"
+        );
+    }
+}
@@ -0,0 +1,723 @@
+//! Master file text for records, checked in both directions.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use dns_rs::{
+    buffer::SliceBuffer,
+    dns::{
+        edns::{Edns, EdnsOption},
+        encoding,
+        name::DomainName,
+        question::DnsQuestion,
+        record::DnsRecord,
+        svcb::SvcParam,
+        DnsClass, DnsPacket, Opcode, QueryType, ResCode,
+    },
+    error::DnsError,
+};
+
+fn name(s: &str) -> DomainName {
+    s.parse().unwrap()
+}
+
+fn assert_text(record: DnsRecord, text: &str) {
+    assert_eq!(record.to_string(), text);
+    assert_eq!(text.parse::<DnsRecord>().unwrap(), record, "parsing {text}");
+}
+
+#[test]
+fn known_types() {
+    assert_text(
+        DnsRecord::A {
+            domain: name("example.com"),
+            addr: Ipv4Addr::new(192, 0, 2, 1),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        "example.com. 300 IN A 192.0.2.1",
+    );
+    assert_text(
+        DnsRecord::AAAA {
+            domain: name("example.com"),
+            addr: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        "example.com. 300 IN AAAA 2001:db8::1",
+    );
+    assert_text(
+        DnsRecord::NS {
+            domain: name("example.com"),
+            host: name("ns1.example.com"),
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        "example.com. 86400 IN NS ns1.example.com.",
+    );
+    assert_text(
+        DnsRecord::CNAME {
+            domain: name("www.example.com"),
+            host: name("example.com"),
+            class: DnsClass::CH,
+            ttl: 0,
+        },
+        "www.example.com. 0 CH CNAME example.com.",
+    );
+    assert_text(
+        DnsRecord::MX {
+            domain: name("example.com"),
+            priority: 10,
+            host: name("mail.example.com"),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        "example.com. 300 IN MX 10 mail.example.com.",
+    );
+    assert_text(
+        DnsRecord::SOA {
+            domain: name("example.com"),
+            mname: name("ns1.example.com"),
+            rname: name("hostmaster.example.com"),
+            serial: 2024010101,
+            refresh: 7200,
+            retry: 3600,
+            expire: 1209600,
+            minimum: 300,
+            class: DnsClass::IN,
+            ttl: 3600,
+        },
+        "example.com. 3600 IN SOA ns1.example.com. hostmaster.example.com. \
+         2024010101 7200 3600 1209600 300",
+    );
+}
+
+#[test]
+fn ptr() {
+    assert_text(
+        DnsRecord::PTR {
+            domain: name("1.2.0.192.in-addr.arpa"),
+            host: name("host.example.com"),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        "1.2.0.192.in-addr.arpa. 300 IN PTR host.example.com.",
+    );
+}
+
+#[test]
+fn srv() {
+    assert_text(
+        DnsRecord::SRV {
+            domain: name("_sip._tcp.example.com"),
+            priority: 10,
+            weight: 60,
+            port: 5060,
+            target: name("sip.example.com"),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        "_sip._tcp.example.com. 300 IN SRV 10 60 5060 sip.example.com.",
+    );
+}
+
+#[test]
+fn naptr_and_uri() {
+    assert_text(
+        DnsRecord::NAPTR {
+            domain: name("4.3.2.1.5.5.5.0.0.8.1.e164.arpa"),
+            order: 100,
+            preference: 10,
+            flags: b"u".to_vec(),
+            services: b"E2U+sip".to_vec(),
+            regexp: br"!^\+1(.*)$!sip:\1@example.com!".to_vec(),
+            class: DnsClass::IN,
+            replacement: DomainName::root(),
+            ttl: 300,
+        },
+        r#"4.3.2.1.5.5.5.0.0.8.1.e164.arpa. 300 IN NAPTR 100 10 "u" "E2U+sip" "!^\\+1(.*)$!sip:\\1@example.com!" ."#,
+    );
+    assert_text(
+        DnsRecord::URI {
+            domain: name("_ftp._tcp.example.com"),
+            priority: 10,
+            weight: 1,
+            target: b"ftp://ftp1.example.com/public".to_vec(),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        r#"_ftp._tcp.example.com. 300 IN URI 10 1 "ftp://ftp1.example.com/public""#,
+    );
+
+    assert!(r#"example.com. 300 IN NAPTR 100 10 "u" "E2U+sip" "!x!y!""#
+        .parse::<DnsRecord>()
+        .is_err());
+    assert!("example.com. 300 IN URI 10 1".parse::<DnsRecord>().is_err());
+}
+
+#[test]
+fn dnssec_types() {
+    assert_text(
+        DnsRecord::DS {
+            domain: name("dskey.example.com"),
+            key_tag: 60485,
+            algorithm: 5,
+            digest_type: 1,
+            digest: encoding::from_hex("2bb183af5f22588179a53b0a98631fad1a292118").unwrap(),
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        "dskey.example.com. 86400 IN DS 60485 5 1 2bb183af5f22588179a53b0a98631fad1a292118",
+    );
+    assert_text(
+        DnsRecord::DNSKEY {
+            domain: name("example.com"),
+            flags: 256,
+            protocol: 3,
+            algorithm: 5,
+            public_key: b"foobar".to_vec(),
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        "example.com. 86400 IN DNSKEY 256 3 5 Zm9vYmFy",
+    );
+    assert_text(
+        DnsRecord::RRSIG {
+            domain: name("host.example.com"),
+            type_covered: QueryType::A,
+            algorithm: 5,
+            labels: 3,
+            original_ttl: 86400,
+            expiration: 1048354263,
+            inception: 1045762263,
+            key_tag: 2642,
+            signer: name("example.com"),
+            signature: b"foob".to_vec(),
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        "host.example.com. 86400 IN RRSIG A 5 3 86400 20030322173103 20030220173103 2642 example.com. Zm9vYg==",
+    );
+    assert_text(
+        DnsRecord::NSEC {
+            domain: name("alfa.example.com"),
+            next: name("host.example.com"),
+            types: vec![
+                QueryType::A,
+                QueryType::MX,
+                QueryType::RRSIG,
+                QueryType::NSEC,
+                QueryType::Unknown(1234),
+            ],
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        "alfa.example.com. 86400 IN NSEC host.example.com. A MX RRSIG NSEC TYPE1234",
+    );
+    assert_text(
+        DnsRecord::NSEC3PARAM {
+            domain: name("example.com"),
+            hash_algorithm: 1,
+            flags: 0,
+            iterations: 0,
+            salt: vec![],
+            class: DnsClass::IN,
+            ttl: 0,
+        },
+        "example.com. 0 IN NSEC3PARAM 1 0 0 -",
+    );
+
+    // Empty digests, keys and hashes are legal on the wire but have no
+    // text form, so they are shown as generic RDATA.
+    assert_text(
+        DnsRecord::DS {
+            domain: name("dskey.example.com"),
+            key_tag: 60485,
+            algorithm: 5,
+            digest_type: 1,
+            digest: vec![],
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        "dskey.example.com. 86400 IN DS \\# 4 ec450501",
+    );
+    assert_text(
+        DnsRecord::DNSKEY {
+            domain: name("example.com"),
+            flags: 256,
+            protocol: 3,
+            algorithm: 5,
+            public_key: vec![],
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        "example.com. 86400 IN DNSKEY \\# 4 01000305",
+    );
+    assert_text(
+        DnsRecord::NSEC3 {
+            domain: name("example.com"),
+            hash_algorithm: 1,
+            flags: 0,
+            iterations: 12,
+            salt: vec![],
+            next_hashed: vec![],
+            types: vec![QueryType::A],
+            class: DnsClass::IN,
+            ttl: 3600,
+        },
+        "example.com. 3600 IN NSEC3 \\# 9 0100000c0000000140",
+    );
+
+    // RFC 5155 appendix A, with the types put in order and base64 split
+    // over several lines elsewhere.
+    let nsec3: DnsRecord =
+        "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example. 3600 IN NSEC3 1 1 12 aabbccdd (
+            2t7b4g4vsa5smi47k61mv5bv1a22bojr MX DNSKEY NS
+            SOA NSEC3PARAM RRSIG )"
+            .parse()
+            .unwrap();
+    assert_eq!(
+        nsec3.to_string(),
+        "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example. 3600 IN NSEC3 1 1 12 aabbccdd 2t7b4g4vsa5smi47k61mv5bv1a22bojr NS SOA MX RRSIG DNSKEY NSEC3PARAM"
+    );
+    let dnskey: DnsRecord = "example.com. 86400 IN DNSKEY 256 3 5 ( Zm9v\n YmFy )"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        dnskey.to_string(),
+        "example.com. 86400 IN DNSKEY 256 3 5 Zm9vYmFy"
+    );
+
+    for bad in [
+        "example.com. 86400 IN DNSKEY 256 3 5 Zm9vYmF",
+        "example.com. 86400 IN DNSKEY 256 3 5",
+        "example.com. 86400 IN DS 60485 5 1 2bb",
+        "example.com. 86400 IN NSEC host.example.com. A BOGUS",
+        "example.com. 86400 IN NSEC3 1 1 12 aabbccdd 2t7b4g4vsa5smi47k61mv5bv1a22boj! A",
+        "example.com. 86400 IN NSEC3 1 1 12 - -",
+        "example.com. 86400 IN NSEC3PARAM 1 0 0 abc",
+    ] {
+        assert!(bad.parse::<DnsRecord>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn sshfp_and_tlsa() {
+    assert_text(
+        DnsRecord::SSHFP {
+            domain: name("host.example.com"),
+            algorithm: 4,
+            fp_type: 2,
+            fingerprint: vec![0x12, 0x34, 0xab, 0xcd],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        "host.example.com. 300 IN SSHFP 4 2 1234abcd",
+    );
+    assert_text(
+        DnsRecord::TLSA {
+            domain: name("_443._tcp.example.com"),
+            usage: 3,
+            selector: 1,
+            matching_type: 1,
+            data: vec![0xde, 0xad, 0xbe, 0xef],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        "_443._tcp.example.com. 300 IN TLSA 3 1 1 deadbeef",
+    );
+
+    // Empty data has no hex form, so it is shown as generic RDATA.
+    assert_text(
+        DnsRecord::SSHFP {
+            domain: name("host.example.com"),
+            algorithm: 4,
+            fp_type: 2,
+            fingerprint: vec![],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        "host.example.com. 300 IN SSHFP \\# 2 0402",
+    );
+
+    // Long fingerprints may be split and use either case.
+    let record: DnsRecord = "host.example.com. 300 IN SSHFP 4 2 ( 1234
+ ABCD )"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        record.to_string(),
+        "host.example.com. 300 IN SSHFP 4 2 1234abcd"
+    );
+    assert!("host.example.com. 300 IN SSHFP 4 2 123"
+        .parse::<DnsRecord>()
+        .is_err());
+    assert!("host.example.com. 300 IN SSHFP 4 2"
+        .parse::<DnsRecord>()
+        .is_err());
+}
+
+#[test]
+fn svcb_and_https() {
+    assert_text(
+        DnsRecord::SVCB {
+            domain: name("example.com"),
+            priority: 16,
+            target: name("foo.example.org"),
+            params: vec![
+                SvcParam::Mandatory(vec![1, 4]),
+                SvcParam::Alpn(vec![b"h2".to_vec(), b"h3-19".to_vec()]),
+                SvcParam::Ipv4Hint(vec![Ipv4Addr::new(192, 0, 2, 1)]),
+            ],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        r#"example.com. 300 IN SVCB 16 foo.example.org. mandatory=alpn,ipv4hint alpn="h2,h3-19" ipv4hint=192.0.2.1"#,
+    );
+    assert_text(
+        DnsRecord::HTTPS {
+            domain: name("example.com"),
+            priority: 1,
+            target: DomainName::root(),
+            params: vec![
+                SvcParam::NoDefaultAlpn,
+                SvcParam::Port(8443),
+                SvcParam::Ech(b"hello".to_vec()),
+                SvcParam::Ipv6Hint(vec![
+                    "2001:db8::1".parse().unwrap(),
+                    "2001:db8::2".parse().unwrap(),
+                ]),
+                SvcParam::Unknown {
+                    key: 667,
+                    value: b"hello\xd2qoo".to_vec(),
+                },
+            ],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        r#"example.com. 300 IN HTTPS 1 . no-default-alpn port=8443 ech=aGVsbG8= ipv6hint=2001:db8::1,2001:db8::2 key667="hello\210qoo""#,
+    );
+    assert_text(
+        DnsRecord::HTTPS {
+            domain: name("example.com"),
+            priority: 0,
+            target: name("svc.example.net"),
+            params: vec![],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        "example.com. 300 IN HTTPS 0 svc.example.net.",
+    );
+
+    // Any order on input, keyNNNNN for known keys, and a comma inside an
+    // alpn id (RFC 9460 appendix D.2, figure 8).
+    let record: DnsRecord =
+        r#"example.com. 300 IN SVCB 16 foo.example.org. key3=53 alpn="f\\\\oo\\,bar,h2" key1234"#
+            .parse()
+            .unwrap();
+    assert_eq!(
+        record,
+        DnsRecord::SVCB {
+            domain: name("example.com"),
+            priority: 16,
+            target: name("foo.example.org"),
+            params: vec![
+                SvcParam::Alpn(vec![b"f\\oo,bar".to_vec(), b"h2".to_vec()]),
+                SvcParam::Port(53),
+                SvcParam::Unknown {
+                    key: 1234,
+                    value: vec![],
+                },
+            ],
+            class: DnsClass::IN,
+            ttl: 300,
+        }
+    );
+    assert_eq!(
+        record.rdata().to_string(),
+        r#"16 foo.example.org. alpn="f\\\\oo\\,bar,h2" port=53 key1234"#
+    );
+
+    for bad in [
+        "example.com. 300 IN HTTPS 1 . port=1 port=2",
+        "example.com. 300 IN HTTPS 1 . port",
+        "example.com. 300 IN HTTPS 1 . port=65536",
+        "example.com. 300 IN HTTPS 1 . alpn=h2,,h3",
+        "example.com. 300 IN HTTPS 1 . mandatory=mandatory",
+        "example.com. 300 IN HTTPS 1 . mandatory=port alpn=h2",
+        "example.com. 300 IN HTTPS 1 . no-default-alpn=x",
+        "example.com. 300 IN HTTPS 1 . ech=notbase64",
+        "example.com. 300 IN HTTPS 1 . bogus=1",
+        "example.com. 300 IN HTTPS 1 . key65535",
+        "example.com. 300 IN HTTPS 1",
+    ] {
+        assert!(bad.parse::<DnsRecord>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn caa() {
+    assert_text(
+        DnsRecord::CAA {
+            domain: name("example.com"),
+            flags: 128,
+            tag: "iodef".to_string(),
+            value: b"mailto:security@example.com".to_vec(),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        r#"example.com. 300 IN CAA 128 iodef "mailto:security@example.com""#,
+    );
+
+    let record: DnsRecord = "example.com. 300 IN CAA 0 issue ca.example.net"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        record.to_string(),
+        r#"example.com. 300 IN CAA 0 issue "ca.example.net""#
+    );
+
+    // The value is not a <character-string>, so it may exceed 255 bytes.
+    let long = format!("example.com. 300 IN CAA 0 issue \"{}\"", "x".repeat(300));
+    assert!(long.parse::<DnsRecord>().is_ok());
+
+    for bad in [
+        "example.com. 300 IN CAA 0 is-sue \"ca.example.net\"",
+        "example.com. 300 IN CAA 0 sixteencharacter \"ca.example.net\"",
+        "example.com. 300 IN CAA 256 issue \"ca.example.net\"",
+        "example.com. 300 IN CAA 0 issue",
+    ] {
+        assert!(bad.parse::<DnsRecord>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn txt_strings() {
+    assert_text(
+        DnsRecord::TXT {
+            domain: name("example.com"),
+            strings: vec![
+                b"v=spf1 include:_spf.example.com".to_vec(),
+                vec![],
+                b"say \"hi\" \\ \x00\xff".to_vec(),
+            ],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        r#"example.com. 300 IN TXT "v=spf1 include:_spf.example.com" "" "say \"hi\" \\ \000\255""#,
+    );
+
+    let record: DnsRecord = "example.com. 300 IN TXT unquoted \"quoted; not a comment\" ; comment"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        record,
+        DnsRecord::TXT {
+            domain: name("example.com"),
+            strings: vec![b"unquoted".to_vec(), b"quoted; not a comment".to_vec()],
+            class: DnsClass::IN,
+            ttl: 300,
+        }
+    );
+
+    let long = format!("example.com. 300 IN TXT \"{}\"", "x".repeat(256));
+    assert!(long.parse::<DnsRecord>().is_err());
+}
+
+#[test]
+fn unknown_type_and_class() {
+    assert_text(
+        DnsRecord::Unknown {
+            domain: name("example.com"),
+            qtype: 65280,
+            data: vec![0xde, 0xad, 0xbe, 0xef],
+            class: DnsClass::Unknown(42),
+            ttl: 60,
+        },
+        "example.com. 60 CLASS42 TYPE65280 \\# 4 deadbeef",
+    );
+    assert_text(
+        DnsRecord::Unknown {
+            domain: DomainName::root(),
+            qtype: 65280,
+            data: vec![],
+            class: DnsClass::IN,
+            ttl: 60,
+        },
+        ". 60 IN TYPE65280 \\# 0",
+    );
+}
+
+#[test]
+fn empty_update_rdata() {
+    // An UPDATE "delete an RRset" entry (RFC 2136 section 2.5.2).
+    assert_text(
+        DnsRecord::Unknown {
+            domain: name("www.example.com"),
+            qtype: 1,
+            data: vec![],
+            class: DnsClass::ANY,
+            ttl: 0,
+        },
+        "www.example.com. 0 ANY A \\# 0",
+    );
+}
+
+#[test]
+fn lenient_input() {
+    let expected = DnsRecord::MX {
+        domain: name("example.com"),
+        priority: 10,
+        host: name("mail.example.com"),
+        class: DnsClass::IN,
+        ttl: 300,
+    };
+
+    let inputs = [
+        "example.com. 300 IN MX 10 mail.example.com.",
+        "example.com IN 300 mx 10 mail.example.com",
+        "example.com. 300 MX 10 mail.example.com. ; comment",
+        "example.com. 300 IN MX (\n  10\n  mail.example.com. )",
+        "example.com. 300 IN TYPE15 10 mail.example.com.",
+        "example.com. 300 IN MX \\# 20 000a 046d61696c 076578616d706c6503636f6d00",
+    ];
+    for input in inputs {
+        assert_eq!(input.parse::<DnsRecord>().unwrap(), expected, "{input}");
+    }
+
+    let record: DnsRecord = "example.com. A 192.0.2.1".parse().unwrap();
+    assert_eq!(record.ttl(), 0);
+    assert_eq!(record.class(), DnsClass::IN);
+}
+
+#[test]
+fn escaped_owner() {
+    let record: DnsRecord = r"a\.b.example.com. 1 IN A 192.0.2.1".parse().unwrap();
+    assert_eq!(record.domain().label_count(), 3);
+    assert_eq!(record.to_string(), r"a\.b.example.com. 1 IN A 192.0.2.1");
+}
+
+#[test]
+fn rejects_bad_text() {
+    let cases = [
+        "",
+        "example.com. 300 IN",
+        "example.com. 300 IN A",
+        "example.com. 300 IN A 192.0.2",
+        "example.com. 300 IN A 192.0.2.1 extra",
+        "example.com. 300 IN MX mail.example.com.",
+        "example.com. 300 IN BOGUS 1",
+        "example.com. 300 IN TYPE65280 dead",
+        "example.com. 300 IN TYPE65280 \\# 3 dead",
+        "example.com. 300 IN A \\# 3 c00002",
+        "example.com. 300 IN TXT",
+        "example.com. 99999999999 IN A 192.0.2.1",
+    ];
+
+    for case in cases {
+        assert!(case.parse::<DnsRecord>().is_err(), "{case}");
+    }
+    assert!(matches!(
+        "example.com. 300 IN BOGUS 1".parse::<DnsRecord>(),
+        Err(DnsError::InvalidRecord(_))
+    ));
+}
+
+#[test]
+fn type_and_class_mnemonics() {
+    assert_eq!(QueryType::MX.to_string(), "MX");
+    assert_eq!(QueryType::Unknown(999).to_string(), "TYPE999");
+    assert_eq!("aaaa".parse::<QueryType>().unwrap(), QueryType::AAAA);
+    assert_eq!("TYPE28".parse::<QueryType>().unwrap(), QueryType::AAAA);
+    assert_eq!(DnsClass::Unknown(7).to_string(), "CLASS7");
+    assert_eq!("CLASS1".parse::<DnsClass>().unwrap(), DnsClass::IN);
+    assert!("TYPE70000".parse::<QueryType>().is_err());
+
+    let question = DnsQuestion::new(name("example.com"), QueryType::A);
+    assert_eq!(question.to_string(), "example.com. IN A");
+}
+
+#[test]
+fn dig_layout() {
+    let mut packet = DnsPacket::default();
+    packet.header.id = 4660;
+    packet.header.response = true;
+    packet.header.recursion_desired = true;
+    packet.header.recursion_available = true;
+    packet.header.rescode = ResCode::NXDOMAIN;
+    packet
+        .questions
+        .push(DnsQuestion::new(name("www.example.com"), QueryType::A));
+    packet.answers.push(
+        "www.example.com. 300 IN CNAME example.com."
+            .parse()
+            .unwrap(),
+    );
+    packet
+        .authorities
+        .push("example.com. 3600 IN NS ns1.example.com.".parse().unwrap());
+    packet.edns = Some(Edns {
+        dnssec_ok: true,
+        options: vec![
+            EdnsOption::Cookie {
+                client: *b"\x01\x23\x45\x67\x89\xab\xcd\xef",
+                server: vec![],
+            },
+            EdnsOption::ClientSubnet {
+                source_prefix: 24,
+                scope_prefix: 0,
+                address: "192.0.2.0".parse().unwrap(),
+            },
+            EdnsOption::Ede {
+                code: 18,
+                text: "Prohibited".to_string(),
+            },
+            EdnsOption::Unknown {
+                code: 65280,
+                data: vec![],
+            },
+        ],
+        ..Edns::default()
+    });
+    packet
+        .resources
+        .push("ns1.example.com. 3600 IN A 192.0.2.53".parse().unwrap());
+
+    assert_eq!(
+        packet.dig().to_string(),
+        ";; ->>HEADER<<- opcode: QUERY, status: NXDOMAIN, id: 4660\n\
+         ;; flags: qr rd ra; QUERY: 1, ANSWER: 1, AUTHORITY: 1, ADDITIONAL: 2\n\
+         \n\
+         ;; OPT PSEUDOSECTION:\n\
+         ; EDNS: version: 0, flags: do; udp: 1232\n\
+         ; COOKIE: 0123456789abcdef\n\
+         ; CLIENT-SUBNET: 192.0.2.0/24/0\n\
+         ; EDE: 18 (\"Prohibited\")\n\
+         ; OPT=65280:\n\
+         \n\
+         ;; QUESTION SECTION:\n\
+         ;www.example.com.\t\tIN\tA\n\
+         \n\
+         ;; ANSWER SECTION:\n\
+         www.example.com.\t300\tIN\tCNAME\texample.com.\n\
+         \n\
+         ;; AUTHORITY SECTION:\n\
+         example.com.\t3600\tIN\tNS\tns1.example.com.\n\
+         \n\
+         ;; ADDITIONAL SECTION:\n\
+         ns1.example.com.\t3600\tIN\tA\t192.0.2.53\n\
+         \n"
+    );
+}
+
+#[test]
+fn dig_extended_rcode() {
+    // An UPDATE with RCODE 0 in the header and 1 in the OPT TTL.
+    let wire = b"\x00\x00\x28\x00\x00\x00\x00\x00\x00\x00\x00\x01\
+                 \x00\x00\x29\x02\x00\x01\x00\x00\x00\x00\x00";
+    let packet = DnsPacket::from_buffer(&mut SliceBuffer::new(wire)).unwrap();
+    assert_eq!(packet.header.opcode, Opcode::UPDATE);
+
+    let text = packet.dig().to_string();
+    assert!(text.starts_with(";; ->>HEADER<<- opcode: UPDATE, status: BADVERS, id: 0\n"));
+    assert!(text.contains("; EDNS: version: 0, flags:; udp: 512\n"));
+}
@@ -0,0 +1,61 @@
+//! Reverse lookup names for IPv4 and IPv6 addresses.
+
+use std::net::IpAddr;
+
+use dns_rs::dns::name::DomainName;
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn ipv4() {
+    let name = DomainName::reverse(ip("192.0.2.1"));
+    assert_eq!(name.to_string(), "1.2.0.192.in-addr.arpa.");
+    assert_eq!(name.reverse_addr(), Some(ip("192.0.2.1")));
+}
+
+#[test]
+fn ipv6() {
+    let name = DomainName::reverse(ip("2001:db8::567:89ab"));
+    assert_eq!(
+        name.to_string(),
+        "b.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa."
+    );
+    assert_eq!(name.reverse_addr(), Some(ip("2001:db8::567:89ab")));
+}
+
+#[test]
+fn parses_any_case() {
+    let name: DomainName =
+        "B.A.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.B.D.0.1.0.0.2.IP6.ARPA"
+            .parse()
+            .unwrap();
+    assert_eq!(name.reverse_addr(), Some(ip("2001:db8::567:89ab")));
+
+    let name: DomainName = "1.2.0.192.IN-ADDR.Arpa.".parse().unwrap();
+    assert_eq!(name.reverse_addr(), Some(ip("192.0.2.1")));
+}
+
+#[test]
+fn rejects_other_names() {
+    let cases = [
+        ".",
+        "arpa.",
+        "example.com.",
+        "2.0.192.in-addr.arpa.",
+        "0.1.2.0.192.in-addr.arpa.",
+        "256.2.0.192.in-addr.arpa.",
+        "01.2.0.192.in-addr.arpa.",
+        "x.2.0.192.in-addr.arpa.",
+        "1.2.0.192.in-addr.example.",
+        "0.8.b.d.0.1.0.0.2.ip6.arpa.",
+        "bb.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa.",
+        "g.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa.",
+    ];
+
+    for case in cases {
+        let name: DomainName = case.parse().unwrap();
+        assert_eq!(name.reverse_addr(), None, "{case}");
+    }
+}
@@ -0,0 +1,571 @@
+//! Property tests: any packet we can build must survive write -> read ->
+//! write unchanged, bit for bit.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use dns_rs::{
+    buffer::{PacketBuffer, SliceBuffer},
+    dns::{
+        edns::{Edns, EdnsOption},
+        encoding,
+        header::DnsHeader,
+        name::DomainName,
+        question::DnsQuestion,
+        record::DnsRecord,
+        svcb::SvcParam,
+        DnsClass, DnsPacket, QueryType, ResCode,
+    },
+};
+use proptest::{
+    collection::{btree_set, vec},
+    prelude::*,
+};
+
+fn arb_label() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        4 => "[a-zA-Z0-9-]{1,12}".prop_map(String::into_bytes),
+        1 => vec(any::<u8>(), 1..=63),
+    ]
+}
+
+fn arb_name() -> impl Strategy<Value = DomainName> {
+    // A small set of parents makes compression kick in regularly.
+    let parent = prop_oneof![
+        Just(vec![]),
+        Just(vec![b"example".to_vec(), b"com".to_vec()]),
+        Just(vec![b"Example".to_vec(), b"ORG".to_vec()]),
+    ];
+    (vec(arb_label(), 0..3), parent).prop_map(|(mut labels, parent)| {
+        labels.extend(parent);
+        DomainName::from_labels(labels).unwrap()
+    })
+}
+
+fn arb_class() -> impl Strategy<Value = DnsClass> {
+    any::<u16>().prop_map(DnsClass::from)
+}
+
+fn arb_header() -> impl Strategy<Value = DnsHeader> {
+    (any::<u16>(), any::<[bool; 8]>(), 0u8..16, 0u8..16).prop_map(|(id, flags, opcode, rcode)| {
+        DnsHeader {
+            id,
+            recursion_desired: flags[0],
+            truncated_message: flags[1],
+            authorative_answer: flags[2],
+            opcode: opcode.into(),
+            response: flags[3],
+            rescode: rcode.into(),
+            checking_disabled: flags[4],
+            authed_data: flags[5],
+            z: flags[6],
+            recursion_available: flags[7],
+            ..DnsHeader::default()
+        }
+    })
+}
+
+fn arb_question() -> impl Strategy<Value = DnsQuestion> {
+    (arb_name(), any::<u16>(), arb_class()).prop_map(|(name, qtype, qclass)| {
+        let mut question = DnsQuestion::new(name, qtype.into());
+        question.qclass = qclass;
+        question
+    })
+}
+
+/// Types in increasing order without repeats, as NSEC records read them.
+fn arb_types() -> impl Strategy<Value = Vec<QueryType>> {
+    btree_set(any::<u16>(), 0..8).prop_map(|types| types.into_iter().map(QueryType::from).collect())
+}
+
+fn arb_svc_param() -> impl Strategy<Value = SvcParam> {
+    prop_oneof![
+        btree_set(1u16..65535, 1..4)
+            .prop_map(|keys| SvcParam::Mandatory(keys.into_iter().collect())),
+        vec(vec(any::<u8>(), 1..8), 1..3).prop_map(SvcParam::Alpn),
+        Just(SvcParam::NoDefaultAlpn),
+        any::<u16>().prop_map(SvcParam::Port),
+        vec(any::<[u8; 4]>().prop_map(Ipv4Addr::from), 1..3).prop_map(SvcParam::Ipv4Hint),
+        vec(any::<u8>(), 0..20).prop_map(SvcParam::Ech),
+        vec(any::<[u8; 16]>().prop_map(Ipv6Addr::from), 1..3).prop_map(SvcParam::Ipv6Hint),
+        (7u16..65535, vec(any::<u8>(), 0..8))
+            .prop_map(|(key, value)| SvcParam::Unknown { key, value }),
+    ]
+}
+
+/// SvcParams in the strictly increasing key order records keep them in.
+fn arb_svc_params() -> impl Strategy<Value = Vec<SvcParam>> {
+    vec(arb_svc_param(), 0..5).prop_map(|mut params| {
+        params.sort_by_key(SvcParam::key);
+        params.dedup_by_key(|param| param.key());
+        // Only keys that are present can be mandatory.
+        let keys: Vec<u16> = params.iter().map(SvcParam::key).collect();
+        if let Some(SvcParam::Mandatory(mandatory)) = params.first_mut() {
+            mandatory.retain(|key| keys.contains(key));
+            if mandatory.is_empty() {
+                params.remove(0);
+            }
+        }
+        params
+    })
+}
+
+fn arb_record() -> impl Strategy<Value = DnsRecord> {
+    let known = [
+        1u16, 2, 5, 6, 12, 15, 16, 28, 33, 35, 43, 44, 46, 47, 48, 50, 51, 52, 64, 65, 256, 257,
+    ];
+    // 41 is OPT, which a packet decodes into `edns`.
+    let unknown_type =
+        any::<u16>().prop_filter("decoded type", move |t| !known.contains(t) && *t != 41);
+
+    (arb_name(), arb_class(), any::<u32>()).prop_flat_map(move |(domain, class, ttl)| {
+        prop_oneof![
+            any::<[u8; 4]>().prop_map({
+                let domain = domain.clone();
+                move |addr| DnsRecord::A {
+                    domain: domain.clone(),
+                    addr: Ipv4Addr::from(addr),
+                    class,
+                    ttl,
+                }
+            }),
+            vec(vec(any::<u8>(), 0..40), 1..4).prop_map({
+                let domain = domain.clone();
+                move |strings| DnsRecord::TXT {
+                    domain: domain.clone(),
+                    strings,
+                    class,
+                    ttl,
+                }
+            }),
+            any::<[u8; 16]>().prop_map({
+                let domain = domain.clone();
+                move |addr| DnsRecord::AAAA {
+                    domain: domain.clone(),
+                    addr: Ipv6Addr::from(addr),
+                    class,
+                    ttl,
+                }
+            }),
+            arb_name().prop_map({
+                let domain = domain.clone();
+                move |host| DnsRecord::NS {
+                    domain: domain.clone(),
+                    host,
+                    class,
+                    ttl,
+                }
+            }),
+            arb_name().prop_map({
+                let domain = domain.clone();
+                move |host| DnsRecord::CNAME {
+                    domain: domain.clone(),
+                    host,
+                    class,
+                    ttl,
+                }
+            }),
+            (arb_name(), arb_name(), any::<[u32; 5]>()).prop_map({
+                let domain = domain.clone();
+                move |(mname, rname, [serial, refresh, retry, expire, minimum])| DnsRecord::SOA {
+                    domain: domain.clone(),
+                    mname,
+                    rname,
+                    serial,
+                    refresh,
+                    retry,
+                    expire,
+                    minimum,
+                    class,
+                    ttl,
+                }
+            }),
+            arb_name().prop_map({
+                let domain = domain.clone();
+                move |host| DnsRecord::PTR {
+                    domain: domain.clone(),
+                    host,
+                    class,
+                    ttl,
+                }
+            }),
+            (any::<u16>(), arb_name()).prop_map({
+                let domain = domain.clone();
+                move |(priority, host)| DnsRecord::MX {
+                    domain: domain.clone(),
+                    priority,
+                    host,
+                    class,
+                    ttl,
+                }
+            }),
+            (any::<[u16; 3]>(), arb_name()).prop_map({
+                let domain = domain.clone();
+                move |([priority, weight, port], target)| DnsRecord::SRV {
+                    domain: domain.clone(),
+                    priority,
+                    weight,
+                    port,
+                    target,
+                    class,
+                    ttl,
+                }
+            }),
+            (
+                any::<[u16; 2]>(),
+                vec(vec(any::<u8>(), 0..20), 3),
+                arb_name(),
+            )
+                .prop_map({
+                    let domain = domain.clone();
+                    move |([order, preference], strings, replacement)| DnsRecord::NAPTR {
+                        domain: domain.clone(),
+                        order,
+                        preference,
+                        flags: strings[0].clone(),
+                        services: strings[1].clone(),
+                        regexp: strings[2].clone(),
+                        replacement,
+                        class,
+                        ttl,
+                    }
+                }),
+            (any::<[u16; 2]>(), vec(any::<u8>(), 0..300)).prop_map({
+                let domain = domain.clone();
+                move |([priority, weight], target)| DnsRecord::URI {
+                    domain: domain.clone(),
+                    priority,
+                    weight,
+                    target,
+                    class,
+                    ttl,
+                }
+            }),
+            (any::<u16>(), any::<[u8; 2]>(), vec(any::<u8>(), 0..40)).prop_map({
+                let domain = domain.clone();
+                move |(key_tag, [algorithm, digest_type], digest)| DnsRecord::DS {
+                    domain: domain.clone(),
+                    key_tag,
+                    algorithm,
+                    digest_type,
+                    digest,
+                    class,
+                    ttl,
+                }
+            }),
+            (
+                any::<(u16, [u8; 2], [u32; 3], u16)>(),
+                arb_name(),
+                vec(any::<u8>(), 0..40),
+            )
+                .prop_map({
+                    let domain = domain.clone();
+                    move |(
+                        (
+                            type_covered,
+                            [algorithm, labels],
+                            [original_ttl, expiration, inception],
+                            key_tag,
+                        ),
+                        signer,
+                        signature,
+                    )| DnsRecord::RRSIG {
+                        domain: domain.clone(),
+                        type_covered: QueryType::from(type_covered),
+                        algorithm,
+                        labels,
+                        original_ttl,
+                        expiration,
+                        inception,
+                        key_tag,
+                        signer,
+                        signature,
+                        class,
+                        ttl,
+                    }
+                }),
+            (arb_name(), arb_types()).prop_map({
+                let domain = domain.clone();
+                move |(next, types)| DnsRecord::NSEC {
+                    domain: domain.clone(),
+                    next,
+                    types,
+                    class,
+                    ttl,
+                }
+            }),
+            (any::<(u16, u8, u8)>(), vec(any::<u8>(), 0..40)).prop_map({
+                let domain = domain.clone();
+                move |((flags, protocol, algorithm), public_key)| DnsRecord::DNSKEY {
+                    domain: domain.clone(),
+                    flags,
+                    protocol,
+                    algorithm,
+                    public_key,
+                    class,
+                    ttl,
+                }
+            }),
+            (
+                any::<(u8, u8, u16)>(),
+                vec(any::<u8>(), 0..20),
+                vec(any::<u8>(), 0..33),
+                arb_types(),
+            )
+                .prop_map({
+                    let domain = domain.clone();
+                    move |((hash_algorithm, flags, iterations), salt, next_hashed, types)| {
+                        DnsRecord::NSEC3 {
+                            domain: domain.clone(),
+                            hash_algorithm,
+                            flags,
+                            iterations,
+                            salt,
+                            next_hashed,
+                            types,
+                            class,
+                            ttl,
+                        }
+                    }
+                }),
+            (any::<(u8, u8, u16)>(), vec(any::<u8>(), 0..20)).prop_map({
+                let domain = domain.clone();
+                move |((hash_algorithm, flags, iterations), salt)| DnsRecord::NSEC3PARAM {
+                    domain: domain.clone(),
+                    hash_algorithm,
+                    flags,
+                    iterations,
+                    salt,
+                    class,
+                    ttl,
+                }
+            }),
+            (any::<[u8; 2]>(), vec(any::<u8>(), 0..40)).prop_map({
+                let domain = domain.clone();
+                move |([algorithm, fp_type], fingerprint)| DnsRecord::SSHFP {
+                    domain: domain.clone(),
+                    algorithm,
+                    fp_type,
+                    fingerprint,
+                    class,
+                    ttl,
+                }
+            }),
+            (any::<[u8; 3]>(), vec(any::<u8>(), 0..40)).prop_map({
+                let domain = domain.clone();
+                move |([usage, selector, matching_type], data)| DnsRecord::TLSA {
+                    domain: domain.clone(),
+                    usage,
+                    selector,
+                    matching_type,
+                    data,
+                    class,
+                    ttl,
+                }
+            }),
+            (any::<u16>(), arb_name(), arb_svc_params()).prop_map({
+                let domain = domain.clone();
+                move |(priority, target, params)| DnsRecord::SVCB {
+                    domain: domain.clone(),
+                    priority,
+                    target,
+                    params,
+                    class,
+                    ttl,
+                }
+            }),
+            (any::<u16>(), arb_name(), arb_svc_params()).prop_map({
+                let domain = domain.clone();
+                move |(priority, target, params)| DnsRecord::HTTPS {
+                    domain: domain.clone(),
+                    priority,
+                    target,
+                    params,
+                    class,
+                    ttl,
+                }
+            }),
+            (any::<u8>(), "[a-zA-Z0-9]{1,15}", vec(any::<u8>(), 0..300)).prop_map({
+                let domain = domain.clone();
+                move |(flags, tag, value)| DnsRecord::CAA {
+                    domain: domain.clone(),
+                    flags,
+                    tag,
+                    value,
+                    class,
+                    ttl,
+                }
+            }),
+            (unknown_type.clone(), vec(any::<u8>(), 0..32)).prop_map({
+                let domain = domain.clone();
+                move |(qtype, data)| DnsRecord::Unknown {
+                    domain: domain.clone(),
+                    qtype,
+                    data,
+                    class,
+                    ttl,
+                }
+            }),
+        ]
+    })
+}
+
+fn arb_edns_option() -> impl Strategy<Value = EdnsOption> {
+    let known = [3u16, 8, 10, 11, 12, 15];
+    prop_oneof![
+        vec(any::<u8>(), 0..16).prop_map(EdnsOption::Nsid),
+        (any::<IpAddr>(), any::<u8>(), any::<u8>()).prop_map(|(address, source, scope)| {
+            let bits = match address {
+                IpAddr::V4(_) => 32,
+                IpAddr::V6(_) => 128,
+            };
+            let source_prefix = source % (bits + 1);
+            let mask = u128::MAX
+                .checked_shl(128 - source_prefix as u32)
+                .unwrap_or(0);
+            let address = match address {
+                IpAddr::V4(addr) => IpAddr::V4((u32::from(addr) & (mask >> 96) as u32).into()),
+                IpAddr::V6(addr) => IpAddr::V6((u128::from(addr) & mask).into()),
+            };
+            EdnsOption::ClientSubnet {
+                source_prefix,
+                scope_prefix: scope % (bits + 1),
+                address,
+            }
+        }),
+        (
+            any::<[u8; 8]>(),
+            prop_oneof![Just(vec![]), vec(any::<u8>(), 8..=32)]
+        )
+            .prop_map(|(client, server)| EdnsOption::Cookie { client, server }),
+        any::<Option<u16>>().prop_map(EdnsOption::Keepalive),
+        (0u16..64).prop_map(EdnsOption::Padding),
+        (any::<u16>(), ".{0,12}").prop_map(|(code, text)| EdnsOption::Ede { code, text }),
+        (
+            any::<u16>().prop_filter("decoded option", move |c| !known.contains(c)),
+            vec(any::<u8>(), 0..16)
+        )
+            .prop_map(|(code, data)| EdnsOption::Unknown { code, data }),
+    ]
+}
+
+fn arb_edns() -> impl Strategy<Value = Edns> {
+    (
+        any::<u16>(),
+        any::<u8>(),
+        any::<u8>(),
+        any::<bool>(),
+        vec(arb_edns_option(), 0..4),
+    )
+        .prop_map(
+            |(udp_payload_size, extended_rcode, version, dnssec_ok, options)| Edns {
+                udp_payload_size,
+                extended_rcode,
+                version,
+                dnssec_ok,
+                options,
+            },
+        )
+}
+
+fn arb_packet() -> impl Strategy<Value = DnsPacket> {
+    (
+        arb_header(),
+        vec(arb_question(), 0..3),
+        vec(arb_record(), 0..4),
+        vec(arb_record(), 0..4),
+        vec(arb_record(), 0..4),
+        proptest::option::of(arb_edns()),
+    )
+        .prop_map(
+            |(mut header, questions, answers, authorities, resources, edns)| {
+                if let Some(edns) = &edns {
+                    header.rescode =
+                        ResCode::from_parts(header.rescode.header_bits(), edns.extended_rcode);
+                }
+                DnsPacket {
+                    header,
+                    questions,
+                    answers,
+                    authorities,
+                    resources,
+                    edns,
+                }
+            },
+        )
+}
+
+fn encode(packet: &mut DnsPacket) -> Vec<u8> {
+    let mut buffer = PacketBuffer::new(0);
+    packet.write(&mut buffer).unwrap();
+    buffer.buf
+}
+
+proptest! {
+    #[test]
+    fn packet_roundtrip(mut packet in arb_packet()) {
+        let bytes = encode(&mut packet);
+        let mut decoded = DnsPacket::from_buffer(&mut SliceBuffer::new(&bytes)).unwrap();
+
+        prop_assert_eq!(&decoded, &packet);
+        prop_assert_eq!(encode(&mut decoded), bytes);
+    }
+
+    #[test]
+    fn uncompressed_writers_agree(mut packet in arb_packet()) {
+        let mut plain = Vec::new();
+        packet.write(&mut plain).unwrap();
+
+        let decoded = DnsPacket::from_buffer(&mut SliceBuffer::new(&plain)).unwrap();
+        prop_assert_eq!(decoded, packet);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_roundtrip(mut packet in arb_packet()) {
+        // RFC 8427 has no member for the Z bit.
+        packet.header.z = false;
+        encode(&mut packet);
+
+        let decoded = DnsPacket::from_json(&packet.to_json().unwrap()).unwrap();
+        prop_assert_eq!(decoded, packet);
+    }
+
+    #[test]
+    fn presentation_roundtrip(record in arb_record()) {
+        let text = record.to_string();
+        let parsed: DnsRecord = text.parse().unwrap();
+
+        prop_assert_eq!(&parsed, &record);
+        prop_assert!(parsed.domain().eq_exact(record.domain()));
+    }
+
+    #[test]
+    fn base64_roundtrip(data in vec(any::<u8>(), 0..64)) {
+        prop_assert_eq!(encoding::from_base64(&encoding::to_base64(&data)), Some(data));
+    }
+
+    #[test]
+    fn reverse_names_roundtrip(addr in any::<IpAddr>()) {
+        prop_assert_eq!(DomainName::reverse(addr).reverse_addr(), Some(addr));
+    }
+
+    #[test]
+    fn arbitrary_bytes_never_panic(bytes in vec(any::<u8>(), 0..128)) {
+        let _ = DnsPacket::from_buffer(&mut SliceBuffer::new(&bytes));
+    }
+}
+
+#[test]
+fn names_survive_with_their_case() {
+    let mut packet = DnsPacket::default();
+    packet.questions.push(DnsQuestion::new(
+        "wWw.ExAmPlE.CoM".parse().unwrap(),
+        QueryType::A,
+    ));
+
+    let bytes = encode(&mut packet);
+    let decoded = DnsPacket::from_buffer(&mut SliceBuffer::new(&bytes)).unwrap();
+    assert!(decoded.questions[0]
+        .name
+        .eq_exact(&packet.questions[0].name));
+}
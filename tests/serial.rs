@@ -0,0 +1,40 @@
+//! RFC 1982 serial number arithmetic.
+
+use std::cmp::Ordering;
+
+use dns_rs::dns::serial::Serial;
+
+#[test]
+fn comparison_wraps() {
+    let cases = [
+        (0, 0, Some(Ordering::Equal)),
+        (1, 0, Some(Ordering::Greater)),
+        (0, 1, Some(Ordering::Less)),
+        (0, u32::MAX, Some(Ordering::Greater)),
+        (u32::MAX, 0, Some(Ordering::Less)),
+        (0x7fff_ffff, 0, Some(Ordering::Greater)),
+        (0x8000_0001, 0, Some(Ordering::Less)),
+        (0x8000_0000, 0, None),
+        (0, 0x8000_0000, None),
+    ];
+
+    for (a, b, expected) in cases {
+        assert_eq!(Serial(a).partial_cmp(&Serial(b)), expected, "{a} vs {b}");
+    }
+}
+
+#[test]
+fn addition() {
+    assert_eq!(Serial(u32::MAX).checked_add(1), Some(Serial(0)));
+    assert_eq!(
+        Serial(5).checked_add(0x7fff_ffff),
+        Some(Serial(0x8000_0004))
+    );
+    assert_eq!(Serial(5).checked_add(0x8000_0000), None);
+
+    let old = Serial(0xffff_fff0);
+    let new = old.checked_add(0x20).unwrap();
+    assert!(new.is_newer_than(old));
+    assert!(!old.is_newer_than(new));
+    assert!(!old.is_newer_than(old));
+}
@@ -0,0 +1,82 @@
+//! RFC 2782 target ordering.
+
+use dns_rs::dns::{record::DnsRecord, srv::sort_targets, DnsClass};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn srv(priority: u16, weight: u16, target: &str) -> DnsRecord {
+    DnsRecord::SRV {
+        domain: "_sip._tcp.example.com".parse().unwrap(),
+        priority,
+        weight,
+        port: 5060,
+        target: target.parse().unwrap(),
+        class: DnsClass::IN,
+        ttl: 300,
+    }
+}
+
+fn target(record: &DnsRecord) -> String {
+    match record {
+        DnsRecord::SRV { target, .. } => target.to_string(),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn priorities_come_first() {
+    let records = [
+        srv(20, 100, "c."),
+        "example.com. 300 IN A 192.0.2.1".parse().unwrap(),
+        srv(10, 0, "a."),
+        srv(30, 1, "d."),
+        srv(10, 5, "b."),
+    ];
+    let mut rng = StdRng::seed_from_u64(1);
+
+    for _ in 0..50 {
+        let priorities: Vec<_> = sort_targets(&records, &mut rng)
+            .into_iter()
+            .map(|record| match record {
+                DnsRecord::SRV { priority, .. } => *priority,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(priorities, [10, 10, 20, 30]);
+    }
+}
+
+#[test]
+fn weights_bias_the_first_pick() {
+    let records = [
+        srv(0, 90, "heavy."),
+        srv(0, 10, "light."),
+        srv(0, 0, "zero."),
+    ];
+    let mut rng = StdRng::seed_from_u64(2);
+
+    let mut heavy = 0;
+    let mut zero = 0;
+    for _ in 0..10_000 {
+        let order = sort_targets(&records, &mut rng);
+        assert_eq!(order.len(), 3);
+        match target(order[0]).as_str() {
+            "heavy." => heavy += 1,
+            "zero." => zero += 1,
+            _ => {}
+        }
+    }
+
+    // Expected shares are 90/101 and 1/101.
+    assert!((8700..9100).contains(&heavy), "{heavy}");
+    assert!((30..250).contains(&zero), "{zero}");
+}
+
+#[test]
+fn lone_root_target_means_unavailable() {
+    let mut rng = StdRng::seed_from_u64(3);
+    assert!(sort_targets(&[srv(0, 0, ".")], &mut rng).is_empty());
+    assert_eq!(
+        sort_targets(&[srv(0, 0, "."), srv(1, 0, "a.")], &mut rng).len(),
+        2
+    );
+}
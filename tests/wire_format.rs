@@ -0,0 +1,1038 @@
+//! Golden wire-format vectors. Every encoding is checked in both directions.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use dns_rs::{
+    buffer::{DnsRead, PacketBuffer, SliceBuffer},
+    dns::{
+        header::DnsHeader, name::DomainName, packet_ref::DnsPacketRef, question::DnsQuestion,
+        record::DnsRecord, svcb::SvcParam, DnsClass, DnsPacket, Opcode, QueryType, ResCode,
+    },
+    error::{DnsError, Malformation, Section},
+};
+
+/// `example.com.` uncompressed.
+const EXAMPLE_COM: &[u8] = b"\x07example\x03com\x00";
+
+fn name(s: &str) -> DomainName {
+    s.parse().unwrap()
+}
+
+fn encode(packet: &mut DnsPacket) -> Vec<u8> {
+    let mut buffer = PacketBuffer::new(0);
+    packet.write(&mut buffer).unwrap();
+    buffer.buf
+}
+
+/// Decodes `bytes`, checking that the borrowed `DnsPacketRef` view agrees
+/// with the owned packet section by section.
+fn decode(bytes: &[u8]) -> Result<DnsPacket, DnsError> {
+    let res = DnsPacket::from_buffer(&mut SliceBuffer::new(bytes));
+    let view = DnsPacketRef::new(bytes);
+    assert_eq!(view.clone().and_then(|view| view.to_packet()), res);
+    let (Ok(packet), Ok(view)) = (&res, view) else {
+        return res;
+    };
+
+    let questions: Vec<_> = view
+        .questions()
+        .map(|question| question.unwrap().to_question())
+        .collect();
+    assert_eq!(questions, packet.questions);
+
+    let sections = [
+        (view.answers().unwrap(), &packet.answers),
+        (view.authorities().unwrap(), &packet.authorities),
+        (view.resources().unwrap(), &packet.resources),
+    ];
+    for (records, expected) in sections {
+        let records: Vec<_> = records.map(Result::unwrap).collect();
+        assert_eq!(records.len(), expected.len());
+        for (record, expected) in records.iter().zip(expected) {
+            assert_eq!(record.domain.to_string(), expected.domain().to_string());
+            assert_eq!(record.qtype, expected.qtype());
+            assert_eq!(record.class, expected.class());
+            assert_eq!(record.ttl, expected.ttl());
+            assert_eq!(&record.to_record().unwrap(), expected);
+        }
+    }
+
+    res
+}
+
+fn concat(parts: &[&[u8]]) -> Vec<u8> {
+    parts.concat()
+}
+
+/// Encodes `record` alone into a fresh buffer, checks it against `expected`
+/// and decodes `expected` back into the same record.
+fn assert_record(record: DnsRecord, expected: &[u8]) {
+    let mut buffer = PacketBuffer::new(0);
+    let len = record.write(&mut buffer).unwrap();
+    assert_eq!(buffer.buf, expected, "encoding {record:?}");
+    assert_eq!(len, expected.len());
+
+    let mut reader = SliceBuffer::new(expected);
+    assert_eq!(DnsRecord::read(&mut reader).unwrap(), record);
+    assert_eq!(reader.pos(), expected.len());
+
+    // And as the only answer of a message, which `decode` also walks with
+    // `DnsPacketRef`.
+    let mut packet = DnsPacket::default();
+    packet.answers.push(record.clone());
+    assert_eq!(decode(&encode(&mut packet)).unwrap().answers, [record]);
+}
+
+type SetFlag = fn(&mut DnsHeader);
+
+#[test]
+fn header_flags() {
+    let cases: [(SetFlag, [u8; 2]); 11] = [
+        (|h| h.response = true, [0x80, 0x00]),
+        (|h| h.opcode = Opcode::STATUS, [0x10, 0x00]),
+        (|h| h.opcode = Opcode::UPDATE, [0x28, 0x00]),
+        (|h| h.authorative_answer = true, [0x04, 0x00]),
+        (|h| h.truncated_message = true, [0x02, 0x00]),
+        (|h| h.recursion_desired = true, [0x01, 0x00]),
+        (|h| h.recursion_available = true, [0x00, 0x80]),
+        (|h| h.z = true, [0x00, 0x40]),
+        (|h| h.authed_data = true, [0x00, 0x20]),
+        (|h| h.checking_disabled = true, [0x00, 0x10]),
+        (|h| h.rescode = ResCode::NXDOMAIN, [0x00, 0x03]),
+    ];
+
+    for (set, flags) in cases {
+        let mut packet = DnsPacket::default();
+        packet.header.id = 0xbeef;
+        set(&mut packet.header);
+
+        let expected = concat(&[&[0xbe, 0xef], &flags, &[0; 8]]);
+        assert_eq!(encode(&mut packet), expected);
+        assert_eq!(decode(&expected).unwrap(), packet);
+    }
+}
+
+#[test]
+fn header_keeps_unknown_opcode_and_rcode() {
+    let bytes = [0x00, 0x01, 0x78, 0x0f, 0, 0, 0, 0, 0, 0, 0, 0];
+    let mut packet = decode(&bytes).unwrap();
+    assert_eq!(packet.header.opcode, Opcode::Unknown(15));
+    assert_eq!(packet.header.rescode, ResCode::Unknown(15));
+    assert_eq!(encode(&mut packet), bytes);
+}
+
+#[test]
+fn query_and_response() {
+    let mut packet = DnsPacket::default();
+    packet.header.id = 0xbeef;
+    packet.header.response = true;
+    packet.header.recursion_desired = true;
+    packet.header.recursion_available = true;
+    packet
+        .questions
+        .push(DnsQuestion::new(name("example.com"), QueryType::A));
+    packet.answers.push(DnsRecord::A {
+        domain: name("example.com"),
+        addr: Ipv4Addr::new(93, 184, 216, 34),
+        class: DnsClass::IN,
+        ttl: 3600,
+    });
+
+    let expected = concat(&[
+        &[
+            0xbe, 0xef, 0x81, 0x80, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+        ],
+        EXAMPLE_COM,
+        &[0x00, 0x01, 0x00, 0x01],
+        &[0xc0, 0x0c, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10],
+        &[0x00, 0x04, 93, 184, 216, 34],
+    ]);
+    assert_eq!(encode(&mut packet), expected);
+    assert_eq!(decode(&expected).unwrap(), packet);
+}
+
+#[test]
+fn question_class() {
+    let mut question = DnsQuestion::new(name("version.bind"), QueryType::Unknown(16));
+    question.qclass = DnsClass::CH;
+
+    let mut buffer = PacketBuffer::new(0);
+    question.write(&mut buffer).unwrap();
+    let expected = concat(&[b"\x07version\x04bind\x00", &[0x00, 0x10, 0x00, 0x03]]);
+    assert_eq!(buffer.buf, expected);
+}
+
+#[test]
+fn record_a() {
+    assert_record(
+        DnsRecord::A {
+            domain: name("example.com"),
+            addr: Ipv4Addr::new(192, 0, 2, 1),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x04],
+            &[192, 0, 2, 1],
+        ]),
+    );
+}
+
+#[test]
+fn record_ns() {
+    assert_record(
+        DnsRecord::NS {
+            domain: name("example.com"),
+            host: name("ns.example.com"),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x05],
+            b"\x02ns\xc0\x00",
+        ]),
+    );
+}
+
+#[test]
+fn record_cname() {
+    assert_record(
+        DnsRecord::CNAME {
+            domain: name("www.example.com"),
+            host: name("example.com"),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            b"\x03www",
+            EXAMPLE_COM,
+            &[0x00, 0x05, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x02],
+            b"\xc0\x04",
+        ]),
+    );
+}
+
+#[test]
+fn record_soa() {
+    assert_record(
+        DnsRecord::SOA {
+            domain: name("example.com"),
+            mname: name("ns1.example.com"),
+            rname: name("hostmaster.example.com"),
+            serial: 2024010101,
+            refresh: 7200,
+            retry: 3600,
+            expire: 1209600,
+            minimum: 300,
+            class: DnsClass::IN,
+            ttl: 3600,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x06, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x27],
+            b"\x03ns1\xc0\x00",
+            b"\x0ahostmaster\xc0\x00",
+            &[
+                0x78, 0xa3, 0xf1, 0x75, 0x00, 0x00, 0x1c, 0x20, 0x00, 0x00, 0x0e, 0x10,
+            ],
+            &[0x00, 0x12, 0x75, 0x00, 0x00, 0x00, 0x01, 0x2c],
+        ]),
+    );
+}
+
+#[test]
+fn record_ptr() {
+    assert_record(
+        DnsRecord::PTR {
+            domain: name("1.2.0.192.in-addr.arpa"),
+            host: name("host.in-addr.arpa"),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            b"\x011\x012\x010\x03192\x07in-addr\x04arpa\x00",
+            &[0x00, 0x0c, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x07],
+            b"\x04host\xc0\x0a",
+        ]),
+    );
+}
+
+#[test]
+fn record_mx() {
+    assert_record(
+        DnsRecord::MX {
+            domain: name("example.com"),
+            priority: 10,
+            host: name("mail.example.com"),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x0f, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x09],
+            b"\x00\x0a\x04mail\xc0\x00",
+        ]),
+    );
+}
+
+#[test]
+fn record_txt() {
+    let long = vec![b'x'; 255];
+    assert_record(
+        DnsRecord::TXT {
+            domain: name("example.com"),
+            strings: vec![b"v=spf1".to_vec(), vec![], b"-all".to_vec(), long.clone()],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x10, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x01, 0x0d],
+            b"\x06v=spf1\x00\x04-all\xff",
+            &long,
+        ]),
+    );
+}
+
+#[test]
+fn txt_strings_are_not_truncated() {
+    let record = DnsRecord::TXT {
+        domain: name("example.com"),
+        strings: vec![vec![b'x'; 256]],
+        class: DnsClass::IN,
+        ttl: 300,
+    };
+    assert_eq!(
+        record.write(&mut PacketBuffer::new(0)),
+        Err(DnsError::StringLengthExceed)
+    );
+}
+
+#[test]
+fn record_aaaa() {
+    assert_record(
+        DnsRecord::AAAA {
+            domain: name("example.com"),
+            addr: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x1c, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x10],
+            &[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+        ]),
+    );
+}
+
+#[test]
+fn record_srv() {
+    // The target is never compressed, even though `example.com` was
+    // already written.
+    assert_record(
+        DnsRecord::SRV {
+            domain: name("_sip._tcp.example.com"),
+            priority: 10,
+            weight: 60,
+            port: 5060,
+            target: name("sip.example.com"),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            b"\x04_sip\x04_tcp",
+            EXAMPLE_COM,
+            &[0x00, 0x21, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x17],
+            &[0x00, 0x0a, 0x00, 0x3c, 0x13, 0xc4],
+            b"\x03sip",
+            EXAMPLE_COM,
+        ]),
+    );
+}
+
+#[test]
+fn record_naptr() {
+    // The replacement is never compressed.
+    assert_record(
+        DnsRecord::NAPTR {
+            domain: name("example.com"),
+            order: 100,
+            preference: 50,
+            flags: b"s".to_vec(),
+            services: b"SIP+D2U".to_vec(),
+            regexp: vec![],
+            replacement: name("_sip._udp.example.com"),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x23, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x26],
+            &[0x00, 0x64, 0x00, 0x32],
+            b"\x01s\x07SIP+D2U\x00",
+            b"\x04_sip\x04_udp",
+            EXAMPLE_COM,
+        ]),
+    );
+}
+
+#[test]
+fn record_uri() {
+    assert_record(
+        DnsRecord::URI {
+            domain: name("_ftp._tcp.example.com"),
+            priority: 10,
+            weight: 1,
+            target: b"ftp://ftp1.example.com/public".to_vec(),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            b"\x04_ftp\x04_tcp",
+            EXAMPLE_COM,
+            &[0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x21],
+            &[0x00, 0x0a, 0x00, 0x01],
+            b"ftp://ftp1.example.com/public",
+        ]),
+    );
+}
+
+#[test]
+fn record_ds() {
+    assert_record(
+        DnsRecord::DS {
+            domain: name("example.com"),
+            key_tag: 60485,
+            algorithm: 5,
+            digest_type: 1,
+            digest: vec![0x2b; 20],
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x2b, 0x00, 0x01, 0x00, 0x01, 0x51, 0x80, 0x00, 0x18],
+            &[0xec, 0x45, 0x05, 0x01],
+            &[0x2b; 20],
+        ]),
+    );
+}
+
+#[test]
+fn record_rrsig() {
+    // The signer is never compressed.
+    assert_record(
+        DnsRecord::RRSIG {
+            domain: name("example.com"),
+            type_covered: QueryType::A,
+            algorithm: 5,
+            labels: 2,
+            original_ttl: 86400,
+            expiration: 1048354263,
+            inception: 1045762263,
+            key_tag: 2642,
+            signer: name("example.com"),
+            signature: vec![1, 2, 3],
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x2e, 0x00, 0x01, 0x00, 0x01, 0x51, 0x80, 0x00, 0x22],
+            &[0x00, 0x01, 0x05, 0x02, 0x00, 0x01, 0x51, 0x80],
+            &[0x3e, 0x7c, 0x9d, 0xd7, 0x3e, 0x55, 0x10, 0xd7, 0x0a, 0x52],
+            EXAMPLE_COM,
+            &[1, 2, 3],
+        ]),
+    );
+}
+
+#[test]
+fn record_nsec() {
+    // RFC 4034 section 4.3.
+    assert_record(
+        DnsRecord::NSEC {
+            domain: name("alfa.example.com"),
+            next: name("host.example.com"),
+            types: vec![
+                QueryType::A,
+                QueryType::MX,
+                QueryType::RRSIG,
+                QueryType::NSEC,
+                QueryType::Unknown(1234),
+            ],
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        &concat(&[
+            b"\x04alfa",
+            EXAMPLE_COM,
+            &[0x00, 0x2f, 0x00, 0x01, 0x00, 0x01, 0x51, 0x80, 0x00, 0x37],
+            b"\x04host",
+            EXAMPLE_COM,
+            &[0x00, 0x06, 0x40, 0x01, 0x00, 0x00, 0x00, 0x03],
+            &[0x04, 0x1b],
+            &[0; 26],
+            &[0x20],
+        ]),
+    );
+}
+
+#[test]
+fn record_dnskey() {
+    assert_record(
+        DnsRecord::DNSKEY {
+            domain: name("example.com"),
+            flags: 257,
+            protocol: 3,
+            algorithm: 8,
+            public_key: vec![1, 2, 3],
+            class: DnsClass::IN,
+            ttl: 86400,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x30, 0x00, 0x01, 0x00, 0x01, 0x51, 0x80, 0x00, 0x07],
+            &[0x01, 0x01, 0x03, 0x08, 1, 2, 3],
+        ]),
+    );
+}
+
+#[test]
+fn record_nsec3() {
+    assert_record(
+        DnsRecord::NSEC3 {
+            domain: name("example.com"),
+            hash_algorithm: 1,
+            flags: 1,
+            iterations: 12,
+            salt: vec![0xaa, 0xbb, 0xcc, 0xdd],
+            next_hashed: vec![0x55; 20],
+            types: vec![QueryType::A, QueryType::RRSIG],
+            class: DnsClass::IN,
+            ttl: 3600,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x32, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x26],
+            &[0x01, 0x01, 0x00, 0x0c, 0x04, 0xaa, 0xbb, 0xcc, 0xdd, 0x14],
+            &[0x55; 20],
+            &[0x00, 0x06, 0x40, 0x00, 0x00, 0x00, 0x00, 0x02],
+        ]),
+    );
+}
+
+#[test]
+fn record_nsec3param() {
+    assert_record(
+        DnsRecord::NSEC3PARAM {
+            domain: name("example.com"),
+            hash_algorithm: 1,
+            flags: 0,
+            iterations: 12,
+            salt: vec![0xaa, 0xbb, 0xcc, 0xdd],
+            class: DnsClass::IN,
+            ttl: 0,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x33, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x09],
+            &[0x01, 0x00, 0x00, 0x0c, 0x04, 0xaa, 0xbb, 0xcc, 0xdd],
+        ]),
+    );
+}
+
+#[test]
+fn record_sshfp() {
+    assert_record(
+        DnsRecord::SSHFP {
+            domain: name("example.com"),
+            algorithm: 4,
+            fp_type: 2,
+            fingerprint: vec![0xab; 32],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x2c, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x22],
+            &[4, 2],
+            &[0xab; 32],
+        ]),
+    );
+}
+
+#[test]
+fn record_tlsa() {
+    assert_record(
+        DnsRecord::TLSA {
+            domain: name("_443._tcp.example.com"),
+            usage: 3,
+            selector: 1,
+            matching_type: 1,
+            data: vec![0x5e; 32],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            b"\x04_443\x04_tcp",
+            EXAMPLE_COM,
+            &[0x00, 0x34, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x23],
+            &[3, 1, 1],
+            &[0x5e; 32],
+        ]),
+    );
+}
+
+#[test]
+fn record_svcb() {
+    // RFC 9460 appendix D.2, figure 5.
+    assert_record(
+        DnsRecord::SVCB {
+            domain: name("example.com"),
+            priority: 16,
+            target: name("foo.example.org"),
+            params: vec![
+                SvcParam::Mandatory(vec![1, 4]),
+                SvcParam::Alpn(vec![b"h2".to_vec(), b"h3-19".to_vec()]),
+                SvcParam::Ipv4Hint(vec![Ipv4Addr::new(192, 0, 2, 1)]),
+            ],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x40, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x30],
+            &[0x00, 0x10],
+            b"\x03foo\x07example\x03org\x00",
+            &[0x00, 0x00, 0x00, 0x04, 0x00, 0x01, 0x00, 0x04],
+            &[0x00, 0x01, 0x00, 0x09, 0x02, b'h', b'2'],
+            &[0x05, b'h', b'3', b'-', b'1', b'9'],
+            &[0x00, 0x04, 0x00, 0x04, 0xc0, 0x00, 0x02, 0x01],
+        ]),
+    );
+}
+
+#[test]
+fn record_https() {
+    // The target is written uncompressed and `.` stands for the owner.
+    assert_record(
+        DnsRecord::HTTPS {
+            domain: name("example.com"),
+            priority: 1,
+            target: DomainName::root(),
+            params: vec![
+                SvcParam::NoDefaultAlpn,
+                SvcParam::Port(8443),
+                SvcParam::Ech(vec![0xfe, 0x0d]),
+                SvcParam::Ipv6Hint(vec!["2001:db8::1".parse().unwrap()]),
+                SvcParam::Unknown {
+                    key: 667,
+                    value: b"hi".to_vec(),
+                },
+            ],
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x00, 0x41, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x2d],
+            &[0x00, 0x01, 0x00],
+            &[0x00, 0x02, 0x00, 0x00],
+            &[0x00, 0x03, 0x00, 0x02, 0x20, 0xfb],
+            &[0x00, 0x05, 0x00, 0x02, 0xfe, 0x0d],
+            &[0x00, 0x06, 0x00, 0x10, 0x20, 0x01, 0x0d, 0xb8],
+            &[0; 11],
+            &[0x01],
+            &[0x02, 0x9b, 0x00, 0x02, b'h', b'i'],
+        ]),
+    );
+}
+
+#[test]
+fn svcb_params_must_be_ordered() {
+    let record = |params| DnsRecord::HTTPS {
+        domain: name("example.com"),
+        priority: 1,
+        target: DomainName::root(),
+        params,
+        class: DnsClass::IN,
+        ttl: 300,
+    };
+
+    let bad = [
+        vec![SvcParam::Port(443), SvcParam::Alpn(vec![b"h2".to_vec()])],
+        vec![SvcParam::Port(443), SvcParam::Port(8443)],
+        vec![SvcParam::Mandatory(vec![3, 1])],
+        vec![SvcParam::Mandatory(vec![0])],
+        vec![
+            SvcParam::Mandatory(vec![3]),
+            SvcParam::Alpn(vec![b"h2".to_vec()]),
+        ],
+        vec![SvcParam::Alpn(vec![])],
+        vec![SvcParam::Alpn(vec![vec![]])],
+        vec![SvcParam::Unknown {
+            key: 3,
+            value: vec![1, 187],
+        }],
+    ];
+    for params in bad {
+        let record = record(params);
+        assert!(
+            record.write(&mut PacketBuffer::new(0)).is_err(),
+            "{record:?}"
+        );
+    }
+}
+
+#[test]
+fn malformed_svcb_params_stay_unknown() {
+    let cases: [&[u8]; 7] = [
+        // Keys out of order.
+        &[0, 3, 0, 2, 1, 187, 0, 1, 0, 3, 2, b'h', b'2'],
+        // Port listed as mandatory but absent.
+        &[0, 0, 0, 2, 0, 3, 0, 1, 0, 3, 2, b'h', b'2'],
+        // A value running past the RDATA.
+        &[0, 3, 0, 4, 1, 187],
+        // Port of the wrong length.
+        &[0, 3, 0, 1, 1],
+        // Empty alpn.
+        &[0, 1, 0, 0],
+        // no-default-alpn with a value.
+        &[0, 2, 0, 1, 0],
+        // The reserved invalid key.
+        &[0xff, 0xff, 0, 0],
+    ];
+    for params in cases {
+        let mut rdata = vec![0, 1, 0];
+        rdata.extend_from_slice(params);
+        let bytes = concat(&[
+            EXAMPLE_COM,
+            &[
+                0x00,
+                0x41,
+                0x00,
+                0x01,
+                0x00,
+                0x00,
+                0x01,
+                0x2c,
+                0x00,
+                rdata.len() as u8,
+            ],
+            &rdata,
+        ]);
+
+        let record = DnsRecord::read(&mut SliceBuffer::new(&bytes)).unwrap();
+        assert_eq!(
+            record,
+            DnsRecord::Unknown {
+                domain: name("example.com"),
+                qtype: 65,
+                data: rdata,
+                class: DnsClass::IN,
+                ttl: 300,
+            }
+        );
+    }
+}
+
+#[test]
+fn record_caa() {
+    assert_record(
+        DnsRecord::CAA {
+            domain: name("example.com"),
+            flags: 128,
+            tag: "issue".to_string(),
+            value: b"ca.example.net".to_vec(),
+            class: DnsClass::IN,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0x01, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x15],
+            &[0x80, 0x05],
+            b"issueca.example.net",
+        ]),
+    );
+}
+
+#[test]
+fn caa_with_bad_tag_stays_unknown() {
+    for tag in [&b""[..], b"is-sue", b"sixteencharacter"] {
+        let mut rdata = vec![0, tag.len() as u8];
+        rdata.extend_from_slice(tag);
+        rdata.extend_from_slice(b"value");
+        let bytes = concat(&[
+            EXAMPLE_COM,
+            &[
+                0x01,
+                0x01,
+                0x00,
+                0x01,
+                0x00,
+                0x00,
+                0x01,
+                0x2c,
+                0x00,
+                rdata.len() as u8,
+            ],
+            &rdata,
+        ]);
+
+        let record = DnsRecord::read(&mut SliceBuffer::new(&bytes)).unwrap();
+        assert_eq!(
+            record,
+            DnsRecord::Unknown {
+                domain: name("example.com"),
+                qtype: 257,
+                data: rdata,
+                class: DnsClass::IN,
+                ttl: 300,
+            }
+        );
+    }
+}
+
+#[test]
+fn record_unknown() {
+    assert_record(
+        DnsRecord::Unknown {
+            domain: name("example.com"),
+            qtype: 65534,
+            data: vec![1, 2, 3],
+            class: DnsClass::CH,
+            ttl: 300,
+        },
+        &concat(&[
+            EXAMPLE_COM,
+            &[0xff, 0xfe, 0x00, 0x03, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x03],
+            &[1, 2, 3],
+        ]),
+    );
+}
+
+#[test]
+fn names_keep_case_and_escapes() {
+    let record = DnsRecord::NS {
+        domain: name("Ex\\.ample.COM"),
+        host: name("ns\\000.example.com"),
+        class: DnsClass::IN,
+        ttl: 0,
+    };
+    assert_record(
+        record,
+        &concat(&[
+            b"\x08Ex.ample\x03COM\x00",
+            &[0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0e],
+            // `com` matches the owner's `COM` regardless of case.
+            b"\x03ns\x00\x07example\xc0\x09",
+        ]),
+    );
+}
+
+#[test]
+fn sections_stay_apart() {
+    let mut packet = DnsPacket::default();
+    let ns = DnsRecord::NS {
+        domain: name("example.com"),
+        host: name("ns.example.com"),
+        class: DnsClass::IN,
+        ttl: 300,
+    };
+    let glue = DnsRecord::A {
+        domain: name("ns.example.com"),
+        addr: Ipv4Addr::new(192, 0, 2, 53),
+        class: DnsClass::IN,
+        ttl: 300,
+    };
+    packet.authorities.push(ns.clone());
+    packet.resources.push(glue.clone());
+
+    let decoded = decode(&encode(&mut packet)).unwrap();
+    assert_eq!(decoded.answers, []);
+    assert_eq!(decoded.authorities, [ns]);
+    assert_eq!(decoded.resources, [glue]);
+}
+
+#[test]
+fn update_deletions() {
+    // Delete the www A RRset, then one A record of it (RFC 2136 section
+    // 2.5.2 and 2.5.4).
+    let delete_rrset = DnsRecord::Unknown {
+        domain: name("www.example.com"),
+        qtype: 1,
+        data: vec![],
+        class: DnsClass::ANY,
+        ttl: 0,
+    };
+    let delete_rr = DnsRecord::A {
+        domain: name("www.example.com"),
+        addr: Ipv4Addr::new(192, 0, 2, 1),
+        class: DnsClass::NONE,
+        ttl: 0,
+    };
+
+    let bytes = concat(&[
+        &[
+            0x12, 0x34, 0x28, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00,
+        ],
+        EXAMPLE_COM,
+        &[0x00, 0x06, 0x00, 0x01],
+        b"\x03www\xc0\x0c",
+        &[0x00, 0x01, 0x00, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        &[0xc0, 0x1d, 0x00, 0x01, 0x00, 0xfe, 0x00, 0x00, 0x00, 0x00],
+        &[0x00, 0x04, 192, 0, 2, 1],
+    ]);
+    let mut packet = decode(&bytes).unwrap();
+    assert_eq!(packet.header.opcode, Opcode::UPDATE);
+    assert_eq!(packet.questions[0].qtype, QueryType::SOA);
+    assert_eq!(packet.authorities, [delete_rrset, delete_rr]);
+    assert_eq!(encode(&mut packet), bytes);
+}
+
+fn assert_malformed(bytes: &[u8], offset: usize, section: Option<Section>, cause: Malformation) {
+    assert_eq!(
+        decode(bytes),
+        Err(DnsError::Malformed {
+            offset,
+            section,
+            cause
+        })
+    );
+}
+
+/// Header announcing a single answer record.
+const ONE_ANSWER: &[u8] = &[0, 0, 0x81, 0x80, 0, 0, 0, 1, 0, 0, 0, 0];
+
+#[test]
+fn rejects_truncated_header() {
+    assert_malformed(
+        &ONE_ANSWER[..7],
+        7,
+        Some(Section::Header),
+        Malformation::Truncated,
+    );
+}
+
+#[test]
+fn rejects_truncated_rdata() {
+    let bytes = concat(&[
+        ONE_ANSWER,
+        b"\x00",
+        &[0, 1, 0, 1, 0, 0, 0, 0, 0, 4],
+        &[1, 2],
+    ]);
+    assert_malformed(
+        &bytes,
+        23,
+        Some(Section::Answer),
+        Malformation::TruncatedRdata { len: 4 },
+    );
+}
+
+#[test]
+fn rejects_rdlength_mismatch() {
+    let bytes = concat(&[
+        ONE_ANSWER,
+        b"\x00",
+        &[0, 1, 0, 1, 0, 0, 0, 0, 0, 5],
+        &[1, 2, 3, 4, 5],
+    ]);
+    assert_malformed(
+        &bytes,
+        23,
+        Some(Section::Answer),
+        Malformation::RdataLengthMismatch {
+            expected: 5,
+            consumed: 4,
+        },
+    );
+}
+
+#[test]
+fn rejects_txt_string_past_rdata() {
+    let bytes = concat(&[
+        ONE_ANSWER,
+        b"\x00",
+        &[0, 16, 0, 1, 0, 0, 0, 0, 0, 3],
+        b"\x03abc",
+    ]);
+    assert_malformed(
+        &bytes,
+        23,
+        Some(Section::Answer),
+        Malformation::RdataLengthMismatch {
+            expected: 3,
+            consumed: 4,
+        },
+    );
+}
+
+#[test]
+fn rejects_forward_and_self_pointers() {
+    let bytes = concat(&[ONE_ANSWER, b"\xc0\x0c"]);
+    assert_malformed(
+        &bytes,
+        12,
+        Some(Section::Answer),
+        Malformation::ForwardPointer { target: 12 },
+    );
+
+    let bytes = concat(&[ONE_ANSWER, b"\xc0\x0e\x00"]);
+    assert_malformed(
+        &bytes,
+        12,
+        Some(Section::Answer),
+        Malformation::ForwardPointer { target: 14 },
+    );
+}
+
+#[test]
+fn rejects_pointer_loops() {
+    // The first name is `a` followed by a pointer back to its own start.
+    let bytes = concat(&[
+        &[0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0],
+        b"\x01a\xc0\x0c",
+        &[0, 1, 0, 1],
+    ]);
+    assert_malformed(
+        &bytes,
+        14,
+        Some(Section::Question),
+        Malformation::PointerLoop { target: 12 },
+    );
+}
+
+#[test]
+fn rejects_reserved_label_types() {
+    for kind in [0x40, 0x80] {
+        let bytes = concat(&[ONE_ANSWER, &[kind | 1, b'a', 0]]);
+        assert_malformed(
+            &bytes,
+            12,
+            Some(Section::Answer),
+            Malformation::ReservedLabelType(kind),
+        );
+    }
+}
+
+#[test]
+fn rejects_long_names() {
+    let label = concat(&[&[63], &[b'a'; 63]]);
+    let bytes = concat(&[ONE_ANSWER, &label, &label, &label, &label, b"\x00"]);
+    assert_malformed(
+        &bytes,
+        12 + 3 * 64,
+        Some(Section::Answer),
+        Malformation::NameTooLong,
+    );
+}
+
+#[test]
+fn rejects_trailing_data() {
+    let bytes = concat(&[&[0; 12], &[0xff]]);
+    assert_malformed(&bytes, 12, None, Malformation::TrailingData(1));
+}